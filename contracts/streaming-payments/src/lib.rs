@@ -10,14 +10,30 @@
 //! - Subscriber can cancel anytime and receive pro-rated refund
 //! - Configurable platform fee deducted from each withdrawal
 //! - Auto-renewal support with grace periods
+//! - Keeper-executable renewals within a subscriber-approved allowance
 //! - Extend existing streams without cancelling
-//! - Creator can terminate problematic subscribers
+//! - Creator can terminate problematic subscribers, settling or fully refunding
+//! - Admin-gated WASM upgrades with batched storage migration
+//! - Storage TTLs kept alive for as long as streams hold escrow
+//! - Emergency pause that halts fund flows but never blocks cancellation
+//! - Permissionless expiry that settles ended streams for their creators
+//! - Admin-managed token allowlist with per-token deposit and duration limits
+//! - On-chain tier catalogue per creator; tiered streams are priced from it
+//! - Subscriber pause and resume within creator-set pause limits
+//! - Creator-offered free trials and refund cliffs, one trial per subscriber
+//! - Linear, stepped or front-loaded release schedules
+//! - Creator payouts routed to a registered payout address
+//! - Revenue splits across a creator's collaborators
+//! - Referrers earn a share of the platform fee and, if creators opt in, of their revenue
+//! - Per-creator fee overrides and per-token volume fee tiers
 
 #![no_std]
+// Contract entry points mirror the on-chain interface, which is wider than clippy's default
+#![allow(clippy::too_many_arguments)]
 
 use soroban_sdk::{
     contract, contractimpl, contracttype, contracterror,
-    Address, Env, Symbol, Vec,
+    Address, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec,
     token::Client as TokenClient,
 };
 
@@ -27,6 +43,40 @@ const BPS_DENOMINATOR: i128 = 10000;
 const DEFAULT_PLATFORM_FEE_BPS: i128 = 200;
 /// Default grace period (24 hours in seconds)
 const DEFAULT_GRACE_PERIOD: u64 = 86400;
+/// Approximate ledgers closed per day (5 second ledgers)
+const LEDGERS_PER_DAY: u32 = 17280;
+/// Average ledger close time in seconds
+const SECONDS_PER_LEDGER: u64 = 5;
+/// Instance storage (admin and fee config) is extended to 30 days once under 7
+const INSTANCE_TTL_THRESHOLD: u32 = 7 * LEDGERS_PER_DAY;
+const INSTANCE_TTL_EXTEND: u32 = 30 * LEDGERS_PER_DAY;
+/// Extra lifetime kept on stream entries past their end time and grace period
+const STREAM_TTL_BUFFER: u32 = 30 * LEDGERS_PER_DAY;
+/// Most entries a paginated query or batch call will visit (a page of
+/// withdrawals through a full revenue split must fit one transaction)
+const MAX_PAGE_SIZE: u32 = 10;
+/// Stream IDs stored per history page entry
+const HISTORY_PAGE_SIZE: u32 = 50;
+/// Most recipients a creator's revenue split may have
+const MAX_SPLIT_RECIPIENTS: u32 = 10;
+/// Storage layout version written by this build
+/// 
+/// History:
+/// 1. Original layout (no stored version)
+/// 2. `Stream.renewal_allowance`
+/// 3. `Stream.fee_bps`
+/// 4. `Stream.accrued` and `Stream.checkpoint_time`
+/// 5. `Stream.termination_reason`
+/// 6. Active stream sets and paged history replace the per-address lists
+/// 7. Active streams keyed by subscriber, creator, tier and token
+/// 8. `Stream.paused_at`, `pause_limit`, `pause_count` and `pause_window_start`
+/// 9. `Stream.trial_end` and `Stream.cliff_end`
+/// 10. `Stream.schedule` and `Stream.accrual_start`
+/// 11. `Stream.referrer`, `referral_fee_bps` and `referral_revenue_bps`
+/// 12. Each stream records its history positions (`HistorySlots`)
+/// 13. Active tier streams counted per subscriber, creator and tier
+/// 14. `Stream.cycle_amount`
+const SCHEMA_VERSION: u32 = 14;
 
 /// Errors that can occur in the streaming payments contract
 #[contracterror]
@@ -45,7 +95,7 @@ pub enum StreamError {
     InvalidDuration = 5,
     /// Insufficient balance to withdraw
     InsufficientBalance = 6,
-    /// Stream already exists between these parties
+    /// Active stream already exists between these parties for this tier and token
     StreamAlreadyExists = 7,
     /// Contract not initialized
     NotInitialized = 8,
@@ -59,6 +109,42 @@ pub enum StreamError {
     RenewalFailed = 12,
     /// Stream already terminated
     AlreadyTerminated = 13,
+    /// Invalid renewal allowance (needs auto-renew, cycles and a positive cap)
+    InvalidAllowance = 14,
+    /// Contract is paused by the admin
+    ContractPaused = 15,
+    /// No admin transfer has been proposed
+    NoPendingAdmin = 16,
+    /// Token is not on the admin-approved allowlist
+    TokenNotAllowed = 17,
+    /// Tier not found in the creator's catalogue
+    TierNotFound = 18,
+    /// Tier is not accepting subscriptions
+    TierNotActive = 19,
+    /// Amount, duration or token does not match the tier
+    TierMismatch = 20,
+    /// Stream has not ended yet (or is still within its renewal grace period)
+    StreamNotEnded = 21,
+    /// Storage migration must finish first (new streams, or a stream `migrate` has not reached)
+    MigrationPending = 22,
+    /// Stream is not paused
+    StreamNotPaused = 23,
+    /// Creator's pause policy allows no further pauses right now
+    PauseLimitReached = 24,
+    /// Trial or cliff is longer than the creator offers
+    TermsNotOffered = 25,
+    /// Subscriber has already had a trial with this creator
+    TrialAlreadyUsed = 26,
+    /// Nothing can be paid out before the stream's cliff
+    CliffNotReached = 27,
+    /// Release schedule has a zero or too long step, or more than 100% up front
+    InvalidSchedule = 28,
+    /// Payout address cannot be the contract itself
+    InvalidPayoutAddress = 29,
+    /// Split needs 1-10 distinct recipients with positive shares summing to 10000 bps
+    InvalidSplit = 30,
+    /// Referrer is a party to the stream, or a referral share is out of bounds
+    InvalidReferral = 31,
 }
 
 /// Status of a payment stream
@@ -73,6 +159,173 @@ pub enum StreamStatus {
     Completed,
     /// Stream was terminated by creator
     Terminated,
+    /// Stream is on hold by the subscriber; nothing accrues until it resumes
+    Paused,
+}
+
+/// How a creator-initiated termination splits the remaining escrow
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TerminationMode {
+    /// Pay the creator what has accrued (minus fee) and refund the rest
+    Settle,
+    /// Refund everything not yet withdrawn; the creator forfeits accrual
+    FullRefund,
+}
+
+/// Renewal budget pre-authorized by the subscriber
+/// 
+/// Lets any keeper renew the stream without a fresh subscriber signature,
+/// pulling funds through a token allowance granted to this contract.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenewalAllowance {
+    /// Number of keeper renewals still available
+    pub remaining_cycles: u32,
+    /// Maximum amount a single renewal may pull from the subscriber
+    pub max_amount_per_cycle: i128,
+}
+
+/// Limits for a token approved for streaming
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenConfig {
+    /// Smallest deposit a new stream may hold
+    pub min_deposit: i128,
+    /// Shortest duration a new stream may run for
+    pub min_duration: u64,
+    /// Largest total a stream may hold, including extensions (None = no cap)
+    pub max_deposit: Option<i128>,
+}
+
+/// A subscription tier in a creator's catalogue
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tier {
+    /// Tier identifier, unique per creator (starts at 1)
+    pub id: u32,
+    /// Token the tier is priced in
+    pub token: Address,
+    /// Amount charged per period
+    pub price: i128,
+    /// Length of one subscription period in seconds
+    pub period_seconds: u64,
+    /// Whether new subscriptions and renewals are accepted
+    pub active: bool,
+}
+
+/// How a stream's deposit is released to the creator over its duration
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum ReleaseSchedule {
+    /// Released continuously, per second
+    #[default]
+    Linear,
+    /// Released in equal chunks at the start of each interval (in seconds)
+    Stepped(u64),
+    /// This many basis points released as soon as accrual starts, the rest linearly
+    FrontLoaded(u32),
+}
+
+/// Limits a creator places on subscribers pausing their streams
+/// 
+/// Zero in any field means no limit.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PausePolicy {
+    /// Longest a single pause may last before the stream resumes by itself
+    pub max_pause_seconds: u64,
+    /// Most pauses a stream may take within one window
+    pub max_pauses: u32,
+    /// Length of the window `max_pauses` counts over (0 = the stream's lifetime)
+    pub period_seconds: u64,
+}
+
+/// Trial and cliff lengths a creator offers to subscribers
+/// 
+/// Zero in a field means that option is not offered.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrialPolicy {
+    /// Longest free trial a stream may start with
+    pub max_trial_seconds: u64,
+    /// Longest cliff a stream may start with
+    pub max_cliff_seconds: u64,
+}
+
+/// Optional terms chosen when a stream is opened
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StreamOptions {
+    /// Free period before accrual starts; the stream runs this much longer
+    pub trial_seconds: u64,
+    /// Period after the trial during which nothing can be paid out and a
+    /// cancel is a full refund (must not exceed the duration)
+    pub cliff_seconds: u64,
+    /// How the deposit is released; kept across renewals
+    pub schedule: ReleaseSchedule,
+    /// Who referred the subscriber; earns referral shares for the stream's life
+    pub referrer: Option<Address>,
+}
+
+/// Fee that applies once a creator's lifetime volume in a token reaches a threshold
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VolumeFeeTier {
+    /// Lifetime volume (gross payouts in the token) at which the tier starts
+    pub min_volume: i128,
+    /// Platform fee in basis points from then on
+    pub fee_bps: i128,
+}
+
+/// Referral terms set by the admin
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReferralConfig {
+    /// Share of the platform fee paid to a stream's referrer, in basis points
+    pub fee_share_bps: u32,
+    /// Largest share of their revenue a creator may offer referrers, in basis points
+    pub max_revenue_share_bps: u32,
+}
+
+/// One recipient's share of a creator's revenue split
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitShare {
+    /// Address paid this share (the creator's own share follows their payout address)
+    pub recipient: Address,
+    /// Share of each payout after platform fees, in basis points
+    pub bps: u32,
+}
+
+/// A revenue split change waiting for a creator's active streams to be settled
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingSplit {
+    /// Split that takes effect once settlement finishes (empty clears the split)
+    pub shares: Vec<SplitShare>,
+    /// Active set slots still to settle, walked from the end down to 0
+    pub remaining: u32,
+}
+
+/// Where an active stream sits in its subscriber's and creator's active sets
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ActiveSlots {
+    /// Slot in the subscriber's active set
+    pub subscriber_slot: u32,
+    /// Slot in the creator's active set
+    pub creator_slot: u32,
+}
+
+/// Where a stream sits in its subscriber's and creator's history
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HistorySlots {
+    /// Index in the subscriber's history
+    pub subscriber_index: u32,
+    /// Index in the creator's history
+    pub creator_index: u32,
 }
 
 /// A streaming payment from subscriber to creator
@@ -89,7 +342,8 @@ pub struct Stream {
     pub token: Address,
     /// Total amount deposited in the stream
     pub total_amount: i128,
-    /// Amount per second being streamed (in stroops)
+    /// Approximate amount per second currently being streamed (in stroops, for
+    /// display; accrual itself is computed exactly from the checkpoint)
     pub rate_per_second: i128,
     /// Unix timestamp when stream started
     pub start_time: u64,
@@ -99,7 +353,7 @@ pub struct Stream {
     pub withdrawn: i128,
     /// Current status of the stream
     pub status: StreamStatus,
-    /// Tier ID (for tracking subscription level)
+    /// Catalogue tier the stream was bought at (0 = custom stream)
     pub tier_id: u32,
     /// Platform wallet for fee collection
     pub platform_wallet: Address,
@@ -107,6 +361,40 @@ pub struct Stream {
     pub auto_renew: bool,
     /// Original duration for renewal calculations
     pub duration_seconds: u64,
+    /// Pre-authorized allowance for keeper renewals (zero cycles = subscriber must sign)
+    pub renewal_allowance: RenewalAllowance,
+    /// Platform fee in basis points, fixed when the stream was created or renewed
+    pub fee_bps: i128,
+    /// Amount accrued to the creator as of `checkpoint_time`
+    pub accrued: i128,
+    /// When `accrued` was last settled; the rest streams linearly from here to `end_time`
+    pub checkpoint_time: u64,
+    /// Creator-supplied reason code if the stream was terminated (0 otherwise)
+    pub termination_reason: u32,
+    /// When the current pause began (0 when not paused)
+    pub paused_at: u64,
+    /// Longest the current pause may last, from the creator's policy (0 = no limit)
+    pub pause_limit: u64,
+    /// Pauses taken in the current pause window
+    pub pause_count: u32,
+    /// When the current pause window began
+    pub pause_window_start: u64,
+    /// When the free trial ends and accrual starts (0 = no trial)
+    pub trial_end: u64,
+    /// Until when nothing can be paid out and a cancel is a full refund (0 = no cliff)
+    pub cliff_end: u64,
+    /// How the deposit is released to the creator
+    pub schedule: ReleaseSchedule,
+    /// When accrual began (after any trial); schedule steps are counted from here
+    pub accrual_start: u64,
+    /// Who referred the subscriber, if anyone
+    pub referrer: Option<Address>,
+    /// Share of the platform fee paid to the referrer, fixed at creation or renewal
+    pub referral_fee_bps: u32,
+    /// Share of creator revenue paid to the referrer, fixed at creation or renewal
+    pub referral_revenue_bps: u32,
+    /// Price of one `duration_seconds` period, charged again when a custom stream renews
+    pub cycle_amount: i128,
 }
 
 /// Storage keys for contract state
@@ -124,14 +412,87 @@ pub enum DataKey {
     NextStreamId,
     /// Stream by ID
     Stream(u64),
-    /// Streams by subscriber (list of stream IDs)
+    /// Page of a subscriber's stream history (up to `HISTORY_PAGE_SIZE` IDs)
+    SubscriberHistory(Address, u32),
+    /// Page of a creator's stream history (up to `HISTORY_PAGE_SIZE` IDs)
+    CreatorHistory(Address, u32),
+    /// Number of streams in a subscriber's history
+    SubscriberHistoryLen(Address),
+    /// Number of streams in a creator's history
+    CreatorHistoryLen(Address),
+    /// Stream ID at a slot of a subscriber's active set
+    SubscriberActive(Address, u32),
+    /// Stream ID at a slot of a creator's active set
+    CreatorActive(Address, u32),
+    /// Size of a subscriber's active set
+    SubscriberActiveLen(Address),
+    /// Size of a creator's active set
+    CreatorActiveLen(Address),
+    /// Active set slots held by a stream
+    ActiveSlots(u64),
+    /// History positions of a stream
+    HistorySlots(u64),
+    /// Active stream between subscriber and creator for a tier and token
+    ActiveStream(Address, Address, u32, Address),
+    /// Revenue split change in progress for a creator
+    PendingSplit(Address),
+    /// Number of active streams between subscriber and creator
+    PairActiveCount(Address, Address),
+    /// Number of active streams a subscriber holds on one of a creator's tiers
+    TierActiveCount(Address, Address, u32),
+    /// Active subscriber count per creator
+    ActiveSubscriberCount(Address),
+    /// Storage layout version the persisted data conforms to
+    SchemaVersion,
+    /// Next stream ID to visit while a migration is in progress
+    MigrationCursor,
+    /// Emergency pause flag
+    Paused,
+    /// Proposed admin awaiting acceptance
+    PendingAdmin,
+    /// Limits for an allowlisted token
+    TokenConfig(Address),
+    /// Allowlisted token addresses
+    AllowedTokens,
+    /// Tier by creator and tier ID
+    Tier(Address, u32),
+    /// Tier IDs in a creator's catalogue
+    CreatorTiers(Address),
+    /// Next tier ID for a creator
+    NextTierId(Address),
+    /// Pause limits set by a creator
+    PausePolicy(Address),
+    /// Trial and cliff terms offered by a creator
+    TrialPolicy(Address),
+    /// Set once a subscriber has started a trial with a creator
+    TrialUsed(Address, Address),
+    /// Where a creator's payouts are sent, if not to the creator
+    PayoutAddress(Address),
+    /// How a creator's payouts are split among collaborators
+    RevenueSplit(Address),
+    /// Referral terms set by the admin
+    ReferralConfig,
+    /// Share of revenue a creator offers referrers
+    CreatorReferralShare(Address),
+    /// Total paid to a referrer in a token
+    ReferralEarnings(Address, Address),
+    /// Platform fee negotiated with a creator, replacing the global fee
+    CreatorFeeOverride(Address),
+    /// Volume fee tiers for a token, by ascending threshold
+    VolumeFeeTiers(Address),
+    /// Gross payouts a creator has received in a token
+    CreatorVolume(Address, Address),
+}
+
+/// Storage keys from older layouts, kept only so `migrate` can clear them
+#[contracttype]
+pub enum LegacyDataKey {
+    /// Streams by subscriber (v1-v5: one unbounded list of stream IDs)
     SubscriberStreams(Address),
-    /// Streams by creator (list of stream IDs)
+    /// Streams by creator (v1-v5: one unbounded list of stream IDs)
     CreatorStreams(Address),
-    /// Active stream between subscriber and creator
+    /// The single active stream between subscriber and creator (v1-v6)
     ActiveStream(Address, Address),
-    /// Active subscriber count per creator
-    ActiveSubscriberCount(Address),
 }
 
 #[contract]
@@ -162,7 +523,7 @@ impl StreamingPaymentsContract {
         
         // Validate fee (max 10%)
         let fee = fee_bps.unwrap_or(DEFAULT_PLATFORM_FEE_BPS);
-        if fee < 0 || fee > 1000 {
+        if !(0..=1000).contains(&fee) {
             panic!("Fee must be 0-1000 basis points");
         }
         
@@ -173,6 +534,8 @@ impl StreamingPaymentsContract {
         env.storage().instance().set(&DataKey::PlatformFeeBps, &fee);
         env.storage().instance().set(&DataKey::GracePeriod, &grace);
         env.storage().instance().set(&DataKey::NextStreamId, &1u64);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        Self::bump_instance(&env);
     }
     
     /// Create a new streaming payment
@@ -180,11 +543,14 @@ impl StreamingPaymentsContract {
     /// # Arguments
     /// * `subscriber` - Address of the payer
     /// * `creator` - Address of the receiver
-    /// * `token` - Token contract address (use native for XLM)
+    /// * `token` - Token contract address, must be allowlisted (native SAC for XLM)
     /// * `amount` - Total amount to stream
     /// * `duration_seconds` - How long to stream (in seconds)
-    /// * `tier_id` - Subscription tier identifier
+    /// * `tier_id` - Catalogue tier (0 for a custom stream); amount, duration
+    ///   and token must then match the tier
     /// * `auto_renew` - Whether to enable auto-renewal
+    /// * `renewal_allowance` - Optional budget keepers may renew against
+    /// * `options` - Optional trial and cliff, within what the creator offers
     /// 
     /// # Returns
     /// The stream ID
@@ -197,83 +563,76 @@ impl StreamingPaymentsContract {
         duration_seconds: u64,
         tier_id: u32,
         auto_renew: bool,
+        renewal_allowance: Option<RenewalAllowance>,
+        options: Option<StreamOptions>,
     ) -> Result<u64, StreamError> {
         // Require subscriber authorization
         subscriber.require_auth();
         
-        // Validate inputs
-        if amount <= 0 {
-            return Err(StreamError::InvalidAmount);
-        }
-        if duration_seconds == 0 {
-            return Err(StreamError::InvalidDuration);
-        }
-        
-        // Check if active stream already exists
-        let key = DataKey::ActiveStream(subscriber.clone(), creator.clone());
-        if env.storage().persistent().has(&key) {
-            return Err(StreamError::StreamAlreadyExists);
-        }
-        
-        // Get next stream ID
-        let stream_id: u64 = env.storage().instance().get(&DataKey::NextStreamId)
-            .ok_or(StreamError::NotInitialized)?;
-        
-        // Get platform wallet
-        let platform_wallet: Address = env.storage().instance().get(&DataKey::PlatformWallet)
-            .ok_or(StreamError::NotInitialized)?;
-        
-        // Calculate rate per second (in stroops for precision)
-        let rate_per_second = amount
-            .checked_div(duration_seconds as i128)
-            .ok_or(StreamError::Overflow)?;
-        
-        // Current timestamp
-        let now = env.ledger().timestamp();
+        Self::require_not_paused(&env)?;
+        Self::validate_allowance(auto_renew, &renewal_allowance)?;
         
-        // Create stream
-        let stream = Stream {
-            id: stream_id,
-            subscriber: subscriber.clone(),
-            creator: creator.clone(),
-            token: token.clone(),
-            total_amount: amount,
-            rate_per_second,
-            start_time: now,
-            end_time: now + duration_seconds,
-            withdrawn: 0,
-            status: StreamStatus::Active,
+        let stream_id = Self::open_stream(
+            &env,
+            &subscriber,
+            &creator,
+            &token,
+            amount,
+            duration_seconds,
             tier_id,
-            platform_wallet,
             auto_renew,
-            duration_seconds,
-        };
+            renewal_allowance.clone(),
+            &options.unwrap_or_default(),
+        )?;
         
         // Transfer tokens from subscriber to contract
         let token_client = TokenClient::new(&env, &token);
         token_client.transfer(&subscriber, &env.current_contract_address(), &amount);
         
-        // Store stream
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
-        env.storage().persistent().set(&key, &stream_id);
+        // Grant the contract an allowance so keepers can renew later
+        if let Some(allowance) = &renewal_allowance {
+            Self::adjust_renewal_approval(&env, &subscriber, &token, Self::allowance_budget(allowance)?)?;
+        }
         
-        // Update subscriber's stream list
-        Self::add_to_address_streams(&env, &subscriber, stream_id, true);
+        Ok(stream_id)
+    }
+    
+    /// Subscribe to a tier from the creator's catalogue
+    /// 
+    /// Amount, duration and token are taken from the tier. Use
+    /// `toggle_auto_renew` afterwards to opt into renewals.
+    /// 
+    /// # Returns
+    /// The stream ID
+    pub fn subscribe_to_tier(
+        env: Env,
+        subscriber: Address,
+        creator: Address,
+        tier_id: u32,
+        options: Option<StreamOptions>,
+    ) -> Result<u64, StreamError> {
+        subscriber.require_auth();
         
-        // Update creator's stream list
-        Self::add_to_address_streams(&env, &creator, stream_id, false);
+        Self::require_not_paused(&env)?;
         
-        // Increment active subscriber count for creator
-        Self::increment_subscriber_count(&env, &creator, true);
+        let tier = Self::active_tier(&env, &creator, tier_id)?;
         
-        // Increment stream ID
-        env.storage().instance().set(&DataKey::NextStreamId, &(stream_id + 1));
+        let stream_id = Self::open_stream(
+            &env,
+            &subscriber,
+            &creator,
+            &tier.token,
+            tier.price,
+            tier.period_seconds,
+            tier_id,
+            false,
+            None,
+            &options.unwrap_or_default(),
+        )?;
         
-        // Emit event
-        env.events().publish(
-            (Symbol::new(&env, "stream_created"), subscriber.clone(), creator.clone()),
-            (stream_id, amount, duration_seconds),
-        );
+        // Transfer tokens from subscriber to contract
+        let token_client = TokenClient::new(&env, &tier.token);
+        token_client.transfer(&subscriber, &env.current_contract_address(), &tier.price);
         
         Ok(stream_id)
     }
@@ -281,39 +640,27 @@ impl StreamingPaymentsContract {
     /// Get the withdrawable amount for a stream
     /// 
     /// This calculates how much the creator can currently withdraw
-    /// based on elapsed time, minus platform fee.
+    /// based on elapsed time, minus platform fee and any referrer's share
+    /// of the creator's revenue.
     pub fn get_withdrawable(env: Env, stream_id: u64) -> Result<i128, StreamError> {
-        let stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)?;
+        let stream = Self::load_stream(&env, stream_id)?;
         
-        if stream.status != StreamStatus::Active {
+        // Same statuses `withdraw` pays, including streams retired by a renewal
+        let now = env.ledger().timestamp();
+        if stream.status == StreamStatus::Cancelled || stream.status == StreamStatus::Terminated {
+            return Ok(0);
+        }
+        if Self::in_cliff(&stream, now) {
             return Ok(0);
         }
         
-        let now = env.ledger().timestamp();
-        let elapsed = if now >= stream.end_time {
-            stream.end_time - stream.start_time
-        } else {
-            now.saturating_sub(stream.start_time)
-        };
-        
-        let earned = stream.rate_per_second
-            .checked_mul(elapsed as i128)
-            .ok_or(StreamError::Overflow)?;
-        
+        let earned = Self::accrued_amount(&stream, now)?;
         let withdrawable = earned.saturating_sub(stream.withdrawn);
         
-        // Get configurable platform fee
-        let fee_bps = Self::get_platform_fee_bps(&env);
-        
-        // Deduct platform fee
-        let fee = withdrawable
-            .checked_mul(fee_bps)
-            .ok_or(StreamError::Overflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(StreamError::Overflow)?;
+        // Deduct platform fee and referral revenue share
+        let (creator_amount, _, _, _) = Self::divide_payout(&env, &stream, withdrawable)?;
         
-        Ok(withdrawable.saturating_sub(fee))
+        Ok(creator_amount)
     }
     
     /// Creator withdraws accrued funds from a stream
@@ -321,74 +668,51 @@ impl StreamingPaymentsContract {
     /// # Returns
     /// Amount withdrawn (after platform fee)
     pub fn withdraw(env: Env, stream_id: u64) -> Result<i128, StreamError> {
-        let mut stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)?;
+        let mut stream = Self::load_stream(&env, stream_id)?;
         
         // Only creator can withdraw
         stream.creator.require_auth();
         
-        if stream.status != StreamStatus::Active && stream.status != StreamStatus::Completed {
+        Self::require_not_paused(&env)?;
+        
+        if stream.status == StreamStatus::Cancelled || stream.status == StreamStatus::Terminated {
             return Err(StreamError::StreamNotActive);
         }
         
         let now = env.ledger().timestamp();
-        let elapsed = if now >= stream.end_time {
-            stream.end_time - stream.start_time
-        } else {
-            now.saturating_sub(stream.start_time)
-        };
-        
-        let earned = stream.rate_per_second
-            .checked_mul(elapsed as i128)
-            .ok_or(StreamError::Overflow)?;
+        if Self::in_cliff(&stream, now) {
+            return Err(StreamError::CliffNotReached);
+        }
         
+        let earned = Self::accrued_amount(&stream, now)?;
         let withdrawable = earned.saturating_sub(stream.withdrawn);
         
         if withdrawable <= 0 {
             return Err(StreamError::InsufficientBalance);
         }
         
-        // Get configurable platform fee
-        let fee_bps = Self::get_platform_fee_bps(&env);
-        
-        // Calculate platform fee
-        let fee = withdrawable
-            .checked_mul(fee_bps)
-            .ok_or(StreamError::Overflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(StreamError::Overflow)?;
-        
-        let creator_amount = withdrawable.saturating_sub(fee);
-        
-        // Transfer to creator
-        let token_client = TokenClient::new(&env, &stream.token);
-        token_client.transfer(&env.current_contract_address(), &stream.creator, &creator_amount);
-        
-        // Transfer fee to platform
-        if fee > 0 {
-            token_client.transfer(&env.current_contract_address(), &stream.platform_wallet, &fee);
-        }
+        // Pay the creator's payout address and the platform fee (the rate is
+        // read first, as the payout's volume can reach a lower tier)
+        let fee_bps = Self::applied_fee_bps(&env, &stream);
+        let (creator_amount, fee, destination) = Self::pay_creator(&env, &stream, withdrawable)?;
         
         // Update stream state
         stream.withdrawn = stream.withdrawn.checked_add(withdrawable)
             .ok_or(StreamError::Overflow)?;
         
-        // Check if stream is complete
-        if now >= stream.end_time {
+        // Check if stream is complete (renewed streams are already retired)
+        if now >= stream.end_time && stream.status == StreamStatus::Active {
             stream.status = StreamStatus::Completed;
             // Remove from active streams
-            let key = DataKey::ActiveStream(stream.subscriber.clone(), stream.creator.clone());
-            env.storage().persistent().remove(&key);
-            // Decrement active subscriber count
-            Self::increment_subscriber_count(&env, &stream.creator, false);
+            Self::deactivate(&env, &stream);
         }
         
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
+        Self::save_stream(&env, &stream);
         
         // Emit event
         env.events().publish(
             (Symbol::new(&env, "withdrawal"), stream.creator.clone()),
-            (stream_id, creator_amount, fee),
+            (stream_id, creator_amount, fee, fee_bps, destination),
         );
         
         Ok(creator_amount)
@@ -399,79 +723,25 @@ impl StreamingPaymentsContract {
     /// # Returns
     /// (creator_received, subscriber_refunded)
     pub fn cancel(env: Env, stream_id: u64) -> Result<(i128, i128), StreamError> {
-        let mut stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)?;
+        let mut stream = Self::load_stream(&env, stream_id)?;
         
         // Only subscriber can cancel
         stream.subscriber.require_auth();
         
-        if stream.status != StreamStatus::Active {
+        if stream.status != StreamStatus::Active && stream.status != StreamStatus::Paused {
             return Err(StreamError::StreamNotActive);
         }
         
-        let now = env.ledger().timestamp();
-        let elapsed = now.saturating_sub(stream.start_time);
-        
-        // Calculate what creator has earned
-        let earned = stream.rate_per_second
-            .checked_mul(elapsed as i128)
-            .ok_or(StreamError::Overflow)?;
-        
-        // Amount not yet withdrawn
-        let pending_to_creator = earned.saturating_sub(stream.withdrawn);
-        
-        // Get configurable platform fee
-        let fee_bps = Self::get_platform_fee_bps(&env);
-        
-        // Calculate fees on pending amount
-        let fee = pending_to_creator
-            .checked_mul(fee_bps)
-            .ok_or(StreamError::Overflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(StreamError::Overflow)?;
-        
-        let creator_amount = pending_to_creator.saturating_sub(fee);
-        
-        // Subscriber gets remaining balance
-        let subscriber_refund = stream.total_amount
-            .saturating_sub(stream.withdrawn)
-            .saturating_sub(pending_to_creator);
-        
-        let token_client = TokenClient::new(&env, &stream.token);
-        
-        // Pay creator what they earned
-        if creator_amount > 0 {
-            token_client.transfer(&env.current_contract_address(), &stream.creator, &creator_amount);
-        }
-        
-        // Pay platform fee
-        if fee > 0 {
-            token_client.transfer(&env.current_contract_address(), &stream.platform_wallet, &fee);
-        }
-        
-        // Refund subscriber
-        if subscriber_refund > 0 {
-            token_client.transfer(&env.current_contract_address(), &stream.subscriber, &subscriber_refund);
-        }
-        
-        // Update stream status
-        stream.status = StreamStatus::Cancelled;
-        stream.withdrawn = stream.withdrawn.checked_add(pending_to_creator)
-            .ok_or(StreamError::Overflow)?;
-        
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
-        
-        // Remove from active streams
-        let key = DataKey::ActiveStream(stream.subscriber.clone(), stream.creator.clone());
-        env.storage().persistent().remove(&key);
-        
-        // Decrement active subscriber count
-        Self::increment_subscriber_count(&env, &stream.creator, false);
+        // Pay creator what they earned and refund the rest (all of it within the cliff)
+        let pay_creator = !Self::in_cliff(&stream, env.ledger().timestamp());
+        let fee_bps = Self::applied_fee_bps(&env, &stream);
+        let (creator_amount, fee, subscriber_refund, destination) =
+            Self::close_stream(&env, &mut stream, StreamStatus::Cancelled, pay_creator)?;
         
         // Emit event
         env.events().publish(
             (Symbol::new(&env, "stream_cancelled"), stream.subscriber.clone()),
-            (stream_id, creator_amount, subscriber_refund),
+            (stream_id, creator_amount, subscriber_refund, fee, fee_bps, destination),
         );
         
         Ok((creator_amount + fee, subscriber_refund))
@@ -479,385 +749,2792 @@ impl StreamingPaymentsContract {
     
     /// Get stream details
     pub fn get_stream(env: Env, stream_id: u64) -> Result<Stream, StreamError> {
-        env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)
+        Self::load_stream(&env, stream_id)
+    }
+    
+    /// Get a page of a subscriber's stream history (oldest first)
+    /// 
+    /// # Arguments
+    /// * `start` - Index into the history to start from
+    /// * `limit` - Page size, capped at `MAX_PAGE_SIZE`
+    pub fn get_subscriber_streams(env: Env, subscriber: Address, start: u32, limit: u32) -> Vec<u64> {
+        Self::history_page(&env, &subscriber, true, start, limit)
+    }
+    
+    /// Get a page of a creator's stream history (oldest first)
+    /// 
+    /// # Arguments
+    /// * `start` - Index into the history to start from
+    /// * `limit` - Page size, capped at `MAX_PAGE_SIZE`
+    pub fn get_creator_streams(env: Env, creator: Address, start: u32, limit: u32) -> Vec<u64> {
+        Self::history_page(&env, &creator, false, start, limit)
+    }
+    
+    /// Get a page of a subscriber's active streams
+    /// 
+    /// The set is unordered and closing a stream moves the last entry into
+    /// its slot, so page through it within one ledger for a consistent view.
+    pub fn get_active_subscriber_streams(env: Env, subscriber: Address, start: u32, limit: u32) -> Vec<u64> {
+        Self::active_page(&env, &subscriber, true, start, limit)
+    }
+    
+    /// Get a page of a creator's active streams
+    /// 
+    /// The set is unordered and closing a stream moves the last entry into
+    /// its slot, so page through it within one ledger for a consistent view.
+    pub fn get_active_creator_streams(env: Env, creator: Address, start: u32, limit: u32) -> Vec<u64> {
+        Self::active_page(&env, &creator, false, start, limit)
+    }
+    
+    /// Get the number of streams a subscriber has ever opened
+    pub fn get_subscriber_stream_count(env: Env, subscriber: Address) -> u32 {
+        Self::read_len(&env, &Self::history_len_key(&subscriber, true))
     }
     
-    /// Get all streams for a subscriber
-    pub fn get_subscriber_streams(env: Env, subscriber: Address) -> Vec<u64> {
-        let key = DataKey::SubscriberStreams(subscriber);
-        env.storage().persistent().get(&key).unwrap_or(Vec::new(&env))
+    /// Get the number of streams a creator has ever received
+    pub fn get_creator_stream_count(env: Env, creator: Address) -> u32 {
+        Self::read_len(&env, &Self::history_len_key(&creator, false))
     }
     
-    /// Get all streams for a creator
-    pub fn get_creator_streams(env: Env, creator: Address) -> Vec<u64> {
-        let key = DataKey::CreatorStreams(creator);
-        env.storage().persistent().get(&key).unwrap_or(Vec::new(&env))
+    /// Get the number of active streams a subscriber is paying into
+    pub fn get_subscriber_active_count(env: Env, subscriber: Address) -> u32 {
+        Self::read_len(&env, &Self::active_len_key(&subscriber, true))
     }
     
-    /// Check if there's an active stream between subscriber and creator
+    /// Check if there's any active stream between subscriber and creator
     pub fn has_active_stream(env: Env, subscriber: Address, creator: Address) -> bool {
-        let key = DataKey::ActiveStream(subscriber, creator);
-        env.storage().persistent().has(&key)
+        Self::get_pair_active_count(&env, &subscriber, &creator) > 0
+    }
+    
+    /// Get the active stream between subscriber and creator for a tier and token
+    pub fn get_active_stream(
+        env: Env,
+        subscriber: Address,
+        creator: Address,
+        tier_id: u32,
+        token: Address,
+    ) -> Option<u64> {
+        let key = DataKey::ActiveStream(subscriber, creator, tier_id, token);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key)
+    }
+    
+    /// Check if the subscriber holds an active stream on one of the creator's tiers
+    /// 
+    /// Counts streams in any token, even if the tier has since been removed.
+    pub fn has_active_tier(env: Env, subscriber: Address, creator: Address, tier_id: u32) -> bool {
+        tier_id != 0 && Self::read_len(&env, &DataKey::TierActiveCount(subscriber, creator, tier_id)) > 0
     }
     
-    /// Get total accrued earnings for a creator across all active streams
-    pub fn get_total_accrued(env: Env, creator: Address) -> i128 {
-        let stream_ids = Self::get_creator_streams(env.clone(), creator);
+    /// Get accrued earnings (after fees) across a page of a creator's active streams
+    /// 
+    /// Pages the active set like `withdraw_all`. Streams that have left it
+    /// with funds still unwithdrawn (such as ones retired by a renewal) are
+    /// quoted by `get_withdrawable`.
+    /// 
+    /// # Arguments
+    /// * `creator` - The creator whose streams are quoted
+    /// * `start` - Index into the creator's active set to start from
+    /// * `limit` - Number of streams to quote, capped at `MAX_PAGE_SIZE`
+    pub fn get_total_accrued(env: Env, creator: Address, start: u32, limit: u32) -> i128 {
+        let stream_ids = Self::active_page(&env, &creator, false, start, limit);
         let mut total: i128 = 0;
         
-        for i in 0..stream_ids.len() {
-            if let Some(id) = stream_ids.get(i) {
-                if let Ok(withdrawable) = Self::get_withdrawable(env.clone(), id) {
-                    total = total.saturating_add(withdrawable);
-                }
+        for id in stream_ids.iter() {
+            if let Ok(withdrawable) = Self::get_withdrawable(env.clone(), id) {
+                total = total.saturating_add(withdrawable);
             }
         }
         
         total
     }
     
-    /// Helper: Add stream ID to address's stream list
-    fn add_to_address_streams(env: &Env, address: &Address, stream_id: u64, is_subscriber: bool) {
-        let key = if is_subscriber {
-            DataKey::SubscriberStreams(address.clone())
-        } else {
-            DataKey::CreatorStreams(address.clone())
-        };
-        
-        let mut streams: Vec<u64> = env.storage().persistent().get(&key)
-            .unwrap_or(Vec::new(env));
-        
-        streams.push_back(stream_id);
-        env.storage().persistent().set(&key, &streams);
+    /// Helper: Read an index length counter
+    fn read_len(env: &Env, key: &DataKey) -> u32 {
+        Self::bump_persistent(env, key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(key).unwrap_or(0)
     }
     
-    /// Helper: Get platform fee in basis points
-    fn get_platform_fee_bps(env: &Env) -> i128 {
-        env.storage().instance().get(&DataKey::PlatformFeeBps)
-            .unwrap_or(DEFAULT_PLATFORM_FEE_BPS)
+    /// Helper: Clamp a `start`/`limit` request to an index of `len` entries
+    fn page_bounds(len: u32, start: u32, limit: u32) -> (u32, u32) {
+        let start = start.min(len);
+        (start, start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(len))
     }
     
-    /// Helper: Get grace period in seconds
-    fn get_grace_period(env: &Env) -> u64 {
-        env.storage().instance().get(&DataKey::GracePeriod)
-            .unwrap_or(DEFAULT_GRACE_PERIOD)
+    /// Helper: Page of an address's stream history, read across history pages
+    fn history_page(env: &Env, address: &Address, is_subscriber: bool, start: u32, limit: u32) -> Vec<u64> {
+        let len = Self::read_len(env, &Self::history_len_key(address, is_subscriber));
+        let (mut index, end) = Self::page_bounds(len, start, limit);
+        
+        let mut stream_ids = Vec::new(env);
+        while index < end {
+            let key = Self::history_key(address, is_subscriber, index / HISTORY_PAGE_SIZE);
+            let page: Vec<u64> = env.storage().persistent().get(&key)
+                .unwrap_or(Vec::new(env));
+            Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+            
+            let offset = index % HISTORY_PAGE_SIZE;
+            let take = (HISTORY_PAGE_SIZE - offset).min(end - index);
+            stream_ids.append(&page.slice(offset..(offset + take).min(page.len())));
+            index += take;
+        }
+        stream_ids
     }
     
-    /// Helper: Increment or decrement active subscriber count for a creator
-    fn increment_subscriber_count(env: &Env, creator: &Address, increment: bool) {
-        let key = DataKey::ActiveSubscriberCount(creator.clone());
-        let current: u64 = env.storage().persistent().get(&key).unwrap_or(0);
-        let new_count = if increment {
-            current.saturating_add(1)
-        } else {
-            current.saturating_sub(1)
-        };
-        env.storage().persistent().set(&key, &new_count);
+    /// Helper: Page of an address's active stream set
+    fn active_page(env: &Env, address: &Address, is_subscriber: bool, start: u32, limit: u32) -> Vec<u64> {
+        let len = Self::read_len(env, &Self::active_len_key(address, is_subscriber));
+        let (start, end) = Self::page_bounds(len, start, limit);
+        
+        let mut stream_ids = Vec::new(env);
+        for slot in start..end {
+            let key = Self::active_key(address, is_subscriber, slot);
+            if let Some(id) = env.storage().persistent().get(&key) {
+                stream_ids.push_back(id);
+            }
+        }
+        stream_ids
     }
     
-    // === Subscriber Functions ===
-    
-    /// Extend an existing stream with additional time and funds
+    /// Helper: Read a stream and extend the TTL of everything it depends on
     /// 
-    /// # Arguments
-    /// * `stream_id` - The stream to extend
-    /// * `additional_amount` - Additional tokens to deposit
-    /// * `additional_seconds` - Additional time to add
-    pub fn extend_stream(
-        env: Env,
-        stream_id: u64,
-        additional_amount: i128,
-        additional_seconds: u64,
-    ) -> Result<(), StreamError> {
+    /// A pause that has outlasted its limit is treated as resumed at the
+    /// limit, so every caller sees accrual picking up again on its own.
+    /// Entries still in an older layout report `MigrationPending` until
+    /// `migrate` reaches them.
+    fn load_stream(env: &Env, stream_id: u64) -> Result<Stream, StreamError> {
+        if Self::migration_pending(env, stream_id) {
+            return Err(StreamError::MigrationPending);
+        }
         let mut stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
             .ok_or(StreamError::StreamNotFound)?;
+        if stream.status == StreamStatus::Paused && stream.pause_limit > 0 {
+            let limit_reached = stream.paused_at.saturating_add(stream.pause_limit);
+            if env.ledger().timestamp() >= limit_reached {
+                Self::resume(&mut stream, limit_reached);
+            }
+        }
+        Self::bump_stream_entries(env, &stream);
+        Ok(stream)
+    }
+    
+    /// Helper: Whether `migrate` has yet to rewrite a stream in the current layout
+    fn migration_pending(env: &Env, stream_id: u64) -> bool {
+        if Self::get_schema_version(env.clone()) >= SCHEMA_VERSION {
+            return false;
+        }
+        let cursor: u64 = env.storage().instance().get(&DataKey::MigrationCursor)
+            .unwrap_or(1);
+        stream_id >= cursor
+    }
+    
+    /// Helper: Write a stream and extend the TTL of everything it depends on
+    fn save_stream(env: &Env, stream: &Stream) {
+        env.storage().persistent().set(&DataKey::Stream(stream.id), stream);
+        Self::bump_stream_entries(env, stream);
+    }
+    
+    /// Helper: Extend a stream entry, its indexes and instance storage
+    fn bump_stream_entries(env: &Env, stream: &Stream) {
+        Self::bump_instance(env);
+        
+        let ttl = Self::stream_ttl(env, stream);
+        Self::bump_persistent(env, &DataKey::Stream(stream.id), ttl);
+        for is_subscriber in [true, false] {
+            let address = if is_subscriber { &stream.subscriber } else { &stream.creator };
+            Self::bump_persistent(env, &Self::history_len_key(address, is_subscriber), ttl);
+            Self::bump_persistent(env, &Self::active_len_key(address, is_subscriber), ttl);
+        }
+        Self::bump_persistent(env, &Self::active_stream_key(stream), ttl);
+        Self::bump_persistent(env, &DataKey::PairActiveCount(stream.subscriber.clone(), stream.creator.clone()), ttl);
+        Self::bump_persistent(env, &DataKey::ActiveSubscriberCount(stream.creator.clone()), ttl);
+        if let Some(slots) = env.storage().persistent().get::<_, HistorySlots>(&DataKey::HistorySlots(stream.id)) {
+            Self::bump_persistent(env, &DataKey::HistorySlots(stream.id), ttl);
+            let subscriber_page = slots.subscriber_index / HISTORY_PAGE_SIZE;
+            let creator_page = slots.creator_index / HISTORY_PAGE_SIZE;
+            Self::bump_persistent(env, &Self::history_key(&stream.subscriber, true, subscriber_page), ttl);
+            Self::bump_persistent(env, &Self::history_key(&stream.creator, false, creator_page), ttl);
+        }
+        if let Some(slots) = env.storage().persistent().get::<_, ActiveSlots>(&DataKey::ActiveSlots(stream.id)) {
+            Self::bump_persistent(env, &DataKey::ActiveSlots(stream.id), ttl);
+            Self::bump_persistent(env, &Self::active_key(&stream.subscriber, true, slots.subscriber_slot), ttl);
+            Self::bump_persistent(env, &Self::active_key(&stream.creator, false, slots.creator_slot), ttl);
+        }
+        if stream.trial_end != 0 {
+            Self::bump_persistent(env, &DataKey::TrialUsed(stream.subscriber.clone(), stream.creator.clone()), ttl);
+        }
+        if stream.tier_id != 0 {
+            let tier_count_key = DataKey::TierActiveCount(stream.subscriber.clone(), stream.creator.clone(), stream.tier_id);
+            Self::bump_persistent(env, &tier_count_key, ttl);
+        }
+    }
+    
+    /// Helper: Extend the creator-wide config a stream's payouts read
+    /// 
+    /// Kept out of `bump_stream_entries` so ordinary reads and writes only
+    /// touch the stream's own entries; payouts bump what they read, and
+    /// keepers cover the rest through `bump_stream`.
+    fn bump_creator_config(env: &Env, stream: &Stream) {
+        let ttl = Self::stream_ttl(env, stream);
+        Self::bump_persistent(env, &DataKey::PayoutAddress(stream.creator.clone()), ttl);
+        Self::bump_persistent(env, &DataKey::RevenueSplit(stream.creator.clone()), ttl);
+        Self::bump_persistent(env, &DataKey::CreatorFeeOverride(stream.creator.clone()), ttl);
+        Self::bump_persistent(env, &DataKey::CreatorVolume(stream.creator.clone(), stream.token.clone()), ttl);
+        if stream.tier_id != 0 {
+            Self::bump_persistent(env, &DataKey::Tier(stream.creator.clone(), stream.tier_id), ttl);
+            Self::bump_persistent(env, &DataKey::CreatorTiers(stream.creator.clone()), ttl);
+        }
+    }
+    
+    /// Helper: Ledgers a stream's entries must stay live for
+    /// 
+    /// Streams still holding escrow live until their end time plus grace
+    /// period (so renewals and late withdrawals can find them), with a buffer
+    /// on top; settled streams only keep the buffer. A paused stream's end
+    /// time moves out by as long as the pause may last.
+    fn stream_ttl(env: &Env, stream: &Stream) -> u32 {
+        let holds_funds = match stream.status {
+            StreamStatus::Active | StreamStatus::Paused => true,
+            StreamStatus::Completed => stream.withdrawn < stream.total_amount,
+            StreamStatus::Cancelled | StreamStatus::Terminated => false,
+        };
         
-        // Only subscriber can extend
-        stream.subscriber.require_auth();
+        let mut end_time = stream.end_time;
+        if stream.status == StreamStatus::Paused {
+            end_time = if stream.pause_limit == 0 {
+                u64::MAX
+            } else {
+                end_time.saturating_add(stream.pause_limit)
+            };
+        }
         
-        if stream.status != StreamStatus::Active {
-            return Err(StreamError::StreamNotActive);
+        let mut ledgers = STREAM_TTL_BUFFER;
+        if holds_funds {
+            let live_until = end_time.saturating_add(Self::get_grace_period(env));
+            let remaining = live_until.saturating_sub(env.ledger().timestamp()) / SECONDS_PER_LEDGER;
+            ledgers = ledgers.saturating_add(remaining.min(u32::MAX as u64) as u32);
         }
         
-        if additional_amount <= 0 {
+        ledgers.min(env.storage().max_ttl())
+    }
+    
+    /// Helper: Extend a persistent entry to `ttl` ledgers if it exists
+    fn bump_persistent(env: &Env, key: &DataKey, ttl: u32) {
+        if env.storage().persistent().has(key) {
+            let ttl = ttl.min(env.storage().max_ttl());
+            // Re-extend at most about once a day per entry
+            env.storage().persistent().extend_ttl(key, ttl.saturating_sub(LEDGERS_PER_DAY), ttl);
+        }
+    }
+    
+    /// Helper: Extend instance storage (admin, fee config, counters)
+    fn bump_instance(env: &Env) {
+        env.storage().instance().extend_ttl(INSTANCE_TTL_THRESHOLD, INSTANCE_TTL_EXTEND);
+    }
+    
+    /// Helper: Fail while the admin has paused the contract
+    fn require_not_paused(env: &Env) -> Result<(), StreamError> {
+        if env.storage().instance().get(&DataKey::Paused).unwrap_or(false) {
+            return Err(StreamError::ContractPaused);
+        }
+        Ok(())
+    }
+    
+    /// Helper: Require the stored admin's authorization
+    fn require_admin(env: &Env) -> Result<Address, StreamError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin)
+            .ok_or(StreamError::NotInitialized)?;
+        admin.require_auth();
+        Self::bump_instance(env);
+        Ok(admin)
+    }
+    
+    /// Helper: Append stream ID to address's history
+    /// 
+    /// Only the last history page is rewritten, so the cost stays constant
+    /// however long the history grows. Returns the stream's history index.
+    fn add_to_address_streams(env: &Env, address: &Address, stream_id: u64, is_subscriber: bool) -> u32 {
+        let len_key = Self::history_len_key(address, is_subscriber);
+        let len: u32 = env.storage().persistent().get(&len_key).unwrap_or(0);
+        
+        let key = Self::history_key(address, is_subscriber, len / HISTORY_PAGE_SIZE);
+        let mut streams: Vec<u64> = env.storage().persistent().get(&key)
+            .unwrap_or(Vec::new(env));
+        
+        streams.push_back(stream_id);
+        env.storage().persistent().set(&key, &streams);
+        env.storage().persistent().set(&len_key, &(len + 1));
+        Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+        len
+    }
+    
+    /// Helper: Find a stream's index in an address's history
+    /// 
+    /// Histories are appended in stream ID order, so a binary search over
+    /// the pages finds it. Only migration needs this; new streams record
+    /// their `HistorySlots` when they are opened.
+    fn find_history_index(env: &Env, address: &Address, is_subscriber: bool, stream_id: u64) -> Option<u32> {
+        let len: u32 = env.storage().persistent().get(&Self::history_len_key(address, is_subscriber))
+            .unwrap_or(0);
+        let page_of = |page: u32| -> Vec<u64> {
+            env.storage().persistent().get(&Self::history_key(address, is_subscriber, page))
+                .unwrap_or(Vec::new(env))
+        };
+        
+        // Last page starting at or before the stream
+        let (mut low, mut high) = (0, len.div_ceil(HISTORY_PAGE_SIZE));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match page_of(mid).first() {
+                Some(first) if first <= stream_id => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        let page = low.checked_sub(1)?;
+        let offset = page_of(page).first_index_of(stream_id)?;
+        Some(page * HISTORY_PAGE_SIZE + offset)
+    }
+    
+    /// Helper: Add stream ID to address's active set, returning its slot
+    fn add_to_active_set(env: &Env, address: &Address, stream_id: u64, is_subscriber: bool) -> u32 {
+        let len_key = Self::active_len_key(address, is_subscriber);
+        let slot: u32 = env.storage().persistent().get(&len_key).unwrap_or(0);
+        
+        env.storage().persistent().set(&Self::active_key(address, is_subscriber, slot), &stream_id);
+        env.storage().persistent().set(&len_key, &(slot + 1));
+        slot
+    }
+    
+    /// Helper: Remove the entry at `slot` from address's active set
+    /// 
+    /// The last entry is moved into the freed slot (and its `ActiveSlots`
+    /// updated) so the set stays dense without shifting.
+    fn remove_from_active_set(env: &Env, address: &Address, slot: u32, is_subscriber: bool) {
+        let len_key = Self::active_len_key(address, is_subscriber);
+        let len: u32 = env.storage().persistent().get(&len_key).unwrap_or(0);
+        if len == 0 {
+            return;
+        }
+        let last = len - 1;
+        
+        if slot < last {
+            let last_key = Self::active_key(address, is_subscriber, last);
+            let moved: u64 = env.storage().persistent().get(&last_key).unwrap_or(0);
+            env.storage().persistent().set(&Self::active_key(address, is_subscriber, slot), &moved);
+            
+            let slots_key = DataKey::ActiveSlots(moved);
+            if let Some(mut slots) = env.storage().persistent().get::<_, ActiveSlots>(&slots_key) {
+                if is_subscriber {
+                    slots.subscriber_slot = slot;
+                } else {
+                    slots.creator_slot = slot;
+                }
+                env.storage().persistent().set(&slots_key, &slots);
+            }
+        }
+        
+        env.storage().persistent().remove(&Self::active_key(address, is_subscriber, last));
+        env.storage().persistent().set(&len_key, &last);
+    }
+    
+    /// Helper: Register a new stream as active for its parties, tier and token
+    fn activate(env: &Env, stream: &Stream) {
+        env.storage().persistent().set(&Self::active_stream_key(stream), &stream.id);
+        
+        Self::add_to_active_sets(env, stream.id, &stream.subscriber, &stream.creator);
+        
+        // A subscriber counts once per creator, however many streams they hold
+        if Self::adjust_pair_count(env, &stream.subscriber, &stream.creator, true) == 1 {
+            Self::increment_subscriber_count(env, &stream.creator, true);
+        }
+        Self::adjust_tier_count(env, stream, stream.tier_id, true);
+    }
+    
+    /// Helper: Add a stream to both parties' active sets and record its slots
+    fn add_to_active_sets(env: &Env, stream_id: u64, subscriber: &Address, creator: &Address) {
+        let slots = ActiveSlots {
+            subscriber_slot: Self::add_to_active_set(env, subscriber, stream_id, true),
+            creator_slot: Self::add_to_active_set(env, creator, stream_id, false),
+        };
+        env.storage().persistent().set(&DataKey::ActiveSlots(stream_id), &slots);
+    }
+    
+    /// Helper: Drop a stream from the active mapping, sets and counts
+    fn deactivate(env: &Env, stream: &Stream) {
+        env.storage().persistent().remove(&Self::active_stream_key(stream));
+        
+        let slots_key = DataKey::ActiveSlots(stream.id);
+        if let Some(slots) = env.storage().persistent().get::<_, ActiveSlots>(&slots_key) {
+            Self::remove_from_active_set(env, &stream.subscriber, slots.subscriber_slot, true);
+            Self::remove_from_active_set(env, &stream.creator, slots.creator_slot, false);
+            env.storage().persistent().remove(&slots_key);
+        }
+        
+        // Decrement active subscriber count once their last stream closes
+        if Self::adjust_pair_count(env, &stream.subscriber, &stream.creator, false) == 0 {
+            Self::increment_subscriber_count(env, &stream.creator, false);
+        }
+        Self::adjust_tier_count(env, stream, stream.tier_id, false);
+    }
+    
+    /// Helper: Key marking a stream as the active one for its parties, tier and token
+    fn active_stream_key(stream: &Stream) -> DataKey {
+        DataKey::ActiveStream(
+            stream.subscriber.clone(),
+            stream.creator.clone(),
+            stream.tier_id,
+            stream.token.clone(),
+        )
+    }
+    
+    /// Helper: Number of active streams between subscriber and creator
+    fn get_pair_active_count(env: &Env, subscriber: &Address, creator: &Address) -> u32 {
+        let key = DataKey::PairActiveCount(subscriber.clone(), creator.clone());
+        Self::read_len(env, &key)
+    }
+    
+    /// Helper: Increment or decrement a pair's active stream count, returning the new count
+    fn adjust_pair_count(env: &Env, subscriber: &Address, creator: &Address, increment: bool) -> u32 {
+        Self::adjust_count(env, &DataKey::PairActiveCount(subscriber.clone(), creator.clone()), increment)
+    }
+    
+    /// Helper: Increment or decrement the stream's count of active streams on `tier_id`
+    fn adjust_tier_count(env: &Env, stream: &Stream, tier_id: u32, increment: bool) {
+        if tier_id != 0 {
+            let key = DataKey::TierActiveCount(stream.subscriber.clone(), stream.creator.clone(), tier_id);
+            Self::adjust_count(env, &key, increment);
+        }
+    }
+    
+    /// Helper: Increment or decrement a stored count (removed at zero), returning the new count
+    fn adjust_count(env: &Env, key: &DataKey, increment: bool) -> u32 {
+        let current: u32 = env.storage().persistent().get(key).unwrap_or(0);
+        let new_count = if increment {
+            current.saturating_add(1)
+        } else {
+            current.saturating_sub(1)
+        };
+        if new_count == 0 {
+            env.storage().persistent().remove(key);
+        } else {
+            env.storage().persistent().set(key, &new_count);
+        }
+        new_count
+    }
+    
+    /// Helper: Key of one page of an address's history
+    fn history_key(address: &Address, is_subscriber: bool, page: u32) -> DataKey {
+        if is_subscriber {
+            DataKey::SubscriberHistory(address.clone(), page)
+        } else {
+            DataKey::CreatorHistory(address.clone(), page)
+        }
+    }
+    
+    /// Helper: Key of an address's history length
+    fn history_len_key(address: &Address, is_subscriber: bool) -> DataKey {
+        if is_subscriber {
+            DataKey::SubscriberHistoryLen(address.clone())
+        } else {
+            DataKey::CreatorHistoryLen(address.clone())
+        }
+    }
+    
+    /// Helper: Key of one slot of an address's active set
+    fn active_key(address: &Address, is_subscriber: bool, slot: u32) -> DataKey {
+        if is_subscriber {
+            DataKey::SubscriberActive(address.clone(), slot)
+        } else {
+            DataKey::CreatorActive(address.clone(), slot)
+        }
+    }
+    
+    /// Helper: Key of an address's active set size
+    fn active_len_key(address: &Address, is_subscriber: bool) -> DataKey {
+        if is_subscriber {
+            DataKey::SubscriberActiveLen(address.clone())
+        } else {
+            DataKey::CreatorActiveLen(address.clone())
+        }
+    }
+    
+    /// Helper: Record a new stream and update indexes (funding is left to the caller)
+    fn open_stream(
+        env: &Env,
+        subscriber: &Address,
+        creator: &Address,
+        token: &Address,
+        amount: i128,
+        duration_seconds: u64,
+        tier_id: u32,
+        auto_renew: bool,
+        renewal_allowance: Option<RenewalAllowance>,
+        options: &StreamOptions,
+    ) -> Result<u64, StreamError> {
+        // Validate inputs
+        if amount <= 0 {
             return Err(StreamError::InvalidAmount);
         }
-        if additional_seconds == 0 {
+        if duration_seconds == 0 {
             return Err(StreamError::InvalidDuration);
         }
         
-        // Transfer additional tokens
-        let token_client = TokenClient::new(&env, &stream.token);
-        token_client.transfer(&stream.subscriber, &env.current_contract_address(), &additional_amount);
+        // Check if an active stream already exists for this tier and token
+        let key = DataKey::ActiveStream(subscriber.clone(), creator.clone(), tier_id, token.clone());
+        if env.storage().persistent().has(&key) {
+            return Err(StreamError::StreamAlreadyExists);
+        }
         
-        // Update stream
-        stream.total_amount = stream.total_amount.checked_add(additional_amount)
+        // Get next stream ID
+        let stream_id: u64 = env.storage().instance().get(&DataKey::NextStreamId)
+            .ok_or(StreamError::NotInitialized)?;
+        
+        // Indexes are rebuilt during migration; new streams would be counted twice
+        if Self::get_schema_version(env.clone()) < SCHEMA_VERSION {
+            return Err(StreamError::MigrationPending);
+        }
+        
+        // Get platform wallet
+        let platform_wallet: Address = env.storage().instance().get(&DataKey::PlatformWallet)
+            .ok_or(StreamError::NotInitialized)?;
+        
+        // Token must be allowlisted and the stream within its limits
+        let config = Self::token_config(env, token)?;
+        if amount < config.min_deposit {
+            return Err(StreamError::InvalidAmount);
+        }
+        if duration_seconds < config.min_duration {
+            return Err(StreamError::InvalidDuration);
+        }
+        Self::check_max_deposit(&config, amount)?;
+        
+        // Tiered streams must be bought at the catalogue price
+        Self::check_tier_terms(env, creator, tier_id, token, amount, duration_seconds)?;
+        
+        Self::validate_schedule(&options.schedule, duration_seconds)?;
+        
+        // Trials and cliffs only as the creator offers them, one trial per subscriber
+        if options.cliff_seconds > duration_seconds {
+            return Err(StreamError::InvalidDuration);
+        }
+        if options.trial_seconds > 0 || options.cliff_seconds > 0 {
+            let offer = Self::get_trial_policy(env.clone(), creator.clone());
+            if options.trial_seconds > offer.max_trial_seconds || options.cliff_seconds > offer.max_cliff_seconds {
+                return Err(StreamError::TermsNotOffered);
+            }
+        }
+        let trial_key = DataKey::TrialUsed(subscriber.clone(), creator.clone());
+        if options.trial_seconds > 0 {
+            if env.storage().persistent().has(&trial_key) {
+                return Err(StreamError::TrialAlreadyUsed);
+            }
+            env.storage().persistent().set(&trial_key, &true);
+        }
+        
+        // Approximate rate per second (display only, accrual is exact)
+        let rate_per_second = amount
+            .checked_div(duration_seconds as i128)
+            .ok_or(StreamError::Overflow)?;
+        
+        // Lock in the platform fee for the stream's whole life
+        let fee_bps = Self::resolve_fee_bps(env, creator, token, None);
+        
+        // Referral terms are locked in the same way; nobody refers themselves
+        let (referral_fee_bps, referral_revenue_bps) = match &options.referrer {
+            Some(referrer) => {
+                if referrer == subscriber || referrer == creator {
+                    return Err(StreamError::InvalidReferral);
+                }
+                let config = Self::get_referral_config(env.clone());
+                let offered = Self::get_creator_referral_share(env.clone(), creator.clone());
+                (config.fee_share_bps, offered.min(config.max_revenue_share_bps))
+            }
+            None => (0, 0),
+        };
+        
+        // Current timestamp; accrual starts once any trial is over
+        let now = env.ledger().timestamp();
+        let accrual_start = now.saturating_add(options.trial_seconds);
+        
+        // Create stream
+        let stream = Stream {
+            id: stream_id,
+            subscriber: subscriber.clone(),
+            creator: creator.clone(),
+            token: token.clone(),
+            total_amount: amount,
+            rate_per_second,
+            start_time: now,
+            end_time: accrual_start.saturating_add(duration_seconds),
+            withdrawn: 0,
+            status: StreamStatus::Active,
+            tier_id,
+            platform_wallet,
+            auto_renew,
+            duration_seconds,
+            renewal_allowance: renewal_allowance.unwrap_or(RenewalAllowance {
+                remaining_cycles: 0,
+                max_amount_per_cycle: 0,
+            }),
+            fee_bps,
+            accrued: 0,
+            checkpoint_time: accrual_start,
+            termination_reason: 0,
+            paused_at: 0,
+            pause_limit: 0,
+            pause_count: 0,
+            pause_window_start: 0,
+            trial_end: if options.trial_seconds > 0 { accrual_start } else { 0 },
+            cliff_end: if options.cliff_seconds > 0 { accrual_start.saturating_add(options.cliff_seconds) } else { 0 },
+            schedule: options.schedule.clone(),
+            accrual_start,
+            referrer: options.referrer.clone(),
+            referral_fee_bps,
+            referral_revenue_bps,
+            cycle_amount: amount,
+        };
+        
+        // Mark as the active stream and add to both active sets
+        Self::activate(env, &stream);
+        
+        // Update subscriber's stream history
+        let subscriber_index = Self::add_to_address_streams(env, subscriber, stream_id, true);
+        
+        // Update creator's stream history
+        let creator_index = Self::add_to_address_streams(env, creator, stream_id, false);
+        env.storage().persistent().set(
+            &DataKey::HistorySlots(stream_id),
+            &HistorySlots { subscriber_index, creator_index },
+        );
+        
+        // Store stream (also extends the TTL of the index entries above)
+        Self::save_stream(env, &stream);
+        
+        // Increment stream ID
+        env.storage().instance().set(&DataKey::NextStreamId, &(stream_id + 1));
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(env, "stream_created"), subscriber.clone(), creator.clone()),
+            (stream_id, amount, duration_seconds, fee_bps),
+        );
+        
+        Ok(stream_id)
+    }
+    
+    /// Helper: Pay out and close an active stream
+    /// 
+    /// The creator's pending accrual is paid (minus fee) when `pay_creator`
+    /// is set; everything else left in escrow is refunded to the subscriber.
+    /// 
+    /// Returns (creator_amount, fee, subscriber_refund, payout_destination).
+    fn close_stream(
+        env: &Env,
+        stream: &mut Stream,
+        status: StreamStatus,
+        pay_creator: bool,
+    ) -> Result<(i128, i128, i128, Address), StreamError> {
+        // Amount earned but not yet withdrawn
+        let earned = Self::accrued_amount(stream, env.ledger().timestamp())?;
+        let pending_to_creator = if pay_creator {
+            earned.saturating_sub(stream.withdrawn)
+        } else {
+            0
+        };
+        
+        // Subscriber gets remaining balance
+        let subscriber_refund = stream.total_amount
+            .saturating_sub(stream.withdrawn)
+            .saturating_sub(pending_to_creator);
+        
+        // Pay creator what they earned, minus fees
+        let (creator_amount, fee, destination) = Self::pay_creator(env, stream, pending_to_creator)?;
+        
+        // Refund subscriber
+        if subscriber_refund > 0 {
+            TokenClient::new(env, &stream.token)
+                .transfer(&env.current_contract_address(), &stream.subscriber, &subscriber_refund);
+        }
+        
+        // Hand back the renewal budget the subscriber approved for this stream
+        Self::release_renewal_budget(env, stream)?;
+        
+        // Update stream status
+        stream.status = status;
+        stream.withdrawn = stream.withdrawn.checked_add(pending_to_creator)
             .ok_or(StreamError::Overflow)?;
-        stream.end_time = stream.end_time + additional_seconds;
-        stream.duration_seconds = stream.duration_seconds + additional_seconds;
+        Self::save_stream(env, stream);
+        
+        // Remove from active streams
+        Self::deactivate(env, stream);
+        
+        Ok((creator_amount, fee, subscriber_refund, destination))
+    }
+    
+    /// Helper: Divide a payout between the creator, the platform and any referrer
+    /// 
+    /// Returns (creator_amount, fee, referral_from_fee, referral_from_revenue);
+    /// the platform keeps `fee - referral_from_fee`.
+    fn divide_payout(env: &Env, stream: &Stream, gross: i128) -> Result<(i128, i128, i128, i128), StreamError> {
+        // Calculate platform fee at the stream's own rate
+        let fee = Self::platform_fee(env, stream, gross)?;
+        let creator_amount = gross.saturating_sub(fee);
+        if stream.referrer.is_none() {
+            return Ok((creator_amount, fee, 0, 0));
+        }
+        
+        // Referrer's cut comes out of the fee and the creator's revenue
+        let from_fee = Self::pro_rata(fee, stream.referral_fee_bps as u64, BPS_DENOMINATOR as u64)?;
+        let from_revenue = Self::pro_rata(creator_amount, stream.referral_revenue_bps as u64, BPS_DENOMINATOR as u64)?;
+        Ok((creator_amount - from_revenue, fee, from_fee, from_revenue))
+    }
+    
+    /// Helper: Pay out `gross` from escrow to the creator, less the platform fee
+    /// 
+    /// Every creator payout goes through here. A referred stream first pays
+    /// its referrer their share of the fee and of the creator's revenue. The
+    /// creator's share is sent to their registered payout address; with a
+    /// revenue split, the rest goes to the collaborators (the last share takes
+    /// any rounding dust).
+    /// 
+    /// Returns (creator_amount, fee, payout_destination).
+    fn pay_creator(env: &Env, stream: &Stream, gross: i128) -> Result<(i128, i128, Address), StreamError> {
+        let (creator_amount, fee, from_fee, from_revenue) = Self::divide_payout(env, stream, gross)?;
+        let platform_amount = fee - from_fee;
+        let destination = Self::payout_address(env, &stream.creator);
+        
+        let token_client = TokenClient::new(env, &stream.token);
+        let contract = env.current_contract_address();
+        
+        if let Some(referrer) = &stream.referrer {
+            let referral = from_fee + from_revenue;
+            if referral > 0 {
+                token_client.transfer(&contract, referrer, &referral);
+                
+                let key = DataKey::ReferralEarnings(referrer.clone(), stream.token.clone());
+                let earned: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+                env.storage().persistent().set(&key, &earned.saturating_add(referral));
+                Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+                
+                // Emit event
+                env.events().publish(
+                    (Symbol::new(env, "referral_paid"), referrer.clone()),
+                    (stream.id, from_fee, from_revenue),
+                );
+            }
+        }
+        
+        let split = Self::revenue_split(env, &stream.creator);
+        if split.is_empty() {
+            if creator_amount > 0 {
+                token_client.transfer(&contract, &destination, &creator_amount);
+            }
+        } else if creator_amount > 0 {
+            let mut remaining = creator_amount;
+            let mut paid: Map<Address, i128> = Map::new(env);
+            for (index, share) in split.iter().enumerate() {
+                let amount = if index as u32 == split.len() - 1 {
+                    remaining
+                } else {
+                    Self::pro_rata(creator_amount, share.bps as u64, BPS_DENOMINATOR as u64)?
+                };
+                remaining -= amount;
+                
+                let to = if share.recipient == stream.creator { destination.clone() } else { share.recipient };
+                if amount > 0 {
+                    token_client.transfer(&contract, &to, &amount);
+                }
+                paid.set(to.clone(), paid.get(to).unwrap_or(0) + amount);
+            }
+            
+            // Emit event
+            env.events().publish(
+                (Symbol::new(env, "split_payout"), stream.creator.clone()),
+                (stream.id, paid),
+            );
+        }
+        if platform_amount > 0 {
+            token_client.transfer(&contract, &stream.platform_wallet, &platform_amount);
+        }
+        
+        // Count towards the creator's volume fee tier
+        if gross > 0 {
+            let key = DataKey::CreatorVolume(stream.creator.clone(), stream.token.clone());
+            let volume: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+            env.storage().persistent().set(&key, &volume.saturating_add(gross));
+            Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+        }
+        
+        Ok((creator_amount, fee, destination))
+    }
+    
+    /// Helper: A creator's revenue split (empty when everything goes to the creator)
+    fn revenue_split(env: &Env, creator: &Address) -> Vec<SplitShare> {
+        let key = DataKey::RevenueSplit(creator.clone());
+        Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key)
+            .unwrap_or(Vec::new(env))
+    }
+    
+    /// Helper: Settle a page of a creator's active streams, then switch splits
+    /// 
+    /// Accrual on each stream is paid under the split still in force, walking
+    /// the active set from the end so streams leaving it don't shift unvisited
+    /// ones. The new split is stored once every slot has been visited; until
+    /// then the change is kept pending, and a call with different shares
+    /// starts the walk over. Returns how many slots are left to settle.
+    fn stage_split(env: &Env, creator: &Address, shares: Vec<SplitShare>, limit: u32) -> u32 {
+        let key = DataKey::PendingSplit(creator.clone());
+        let len = Self::read_len(env, &Self::active_len_key(creator, false));
+        let mut pending = match env.storage().persistent().get::<_, PendingSplit>(&key) {
+            Some(pending) if pending.shares == shares => pending,
+            _ => PendingSplit { shares, remaining: len },
+        };
+        
+        // Streams that left the set since the last page need no visit
+        let end = pending.remaining.min(len);
+        let start = end.saturating_sub(limit.min(MAX_PAGE_SIZE));
+        for slot in (start..end).rev() {
+            if let Some(id) = env.storage().persistent().get::<_, u64>(&Self::active_key(creator, false, slot)) {
+                // Nothing to pay (or still within a cliff) is fine
+                let _ = Self::withdraw(env.clone(), id);
+            }
+        }
+        pending.remaining = start;
+        
+        if pending.remaining > 0 {
+            env.storage().persistent().set(&key, &pending);
+            Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+            return pending.remaining;
+        }
+        
+        env.storage().persistent().remove(&key);
+        let split_key = DataKey::RevenueSplit(creator.clone());
+        if pending.shares.is_empty() {
+            env.storage().persistent().remove(&split_key);
+        } else {
+            env.storage().persistent().set(&split_key, &pending.shares);
+            Self::bump_persistent(env, &split_key, STREAM_TTL_BUFFER);
+        }
+        0
+    }
+    
+    /// Helper: Where a creator's payouts go (the creator unless they registered another address)
+    fn payout_address(env: &Env, creator: &Address) -> Address {
+        let key = DataKey::PayoutAddress(creator.clone());
+        Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key)
+            .unwrap_or(creator.clone())
+    }
+    
+    /// Helper: Limits for an allowlisted token
+    fn token_config(env: &Env, token: &Address) -> Result<TokenConfig, StreamError> {
+        env.storage().instance().get(&DataKey::TokenConfig(token.clone()))
+            .ok_or(StreamError::TokenNotAllowed)
+    }
+    
+    /// Helper: Load a tier from a creator's catalogue
+    fn load_tier(env: &Env, creator: &Address, tier_id: u32) -> Result<Tier, StreamError> {
+        let key = DataKey::Tier(creator.clone(), tier_id);
+        let tier: Tier = env.storage().persistent().get(&key)
+            .ok_or(StreamError::TierNotFound)?;
+        Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+        Ok(tier)
+    }
+    
+    /// Helper: Load a tier that is accepting subscriptions
+    fn active_tier(env: &Env, creator: &Address, tier_id: u32) -> Result<Tier, StreamError> {
+        let tier = Self::load_tier(env, creator, tier_id)?;
+        if !tier.active {
+            return Err(StreamError::TierNotActive);
+        }
+        Ok(tier)
+    }
+    
+    /// Helper: Check that stream terms match the claimed tier (0 = custom, anything goes)
+    fn check_tier_terms(
+        env: &Env,
+        creator: &Address,
+        tier_id: u32,
+        token: &Address,
+        amount: i128,
+        duration_seconds: u64,
+    ) -> Result<(), StreamError> {
+        if tier_id != 0 {
+            let tier = Self::active_tier(env, creator, tier_id)?;
+            if tier.token != *token || tier.price != amount || tier.period_seconds != duration_seconds {
+                return Err(StreamError::TierMismatch);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Helper: Check tier pricing against the token's limits
+    fn validate_tier_terms(env: &Env, token: &Address, price: i128, period_seconds: u64) -> Result<(), StreamError> {
+        if price <= 0 {
+            return Err(StreamError::InvalidAmount);
+        }
+        if period_seconds == 0 {
+            return Err(StreamError::InvalidDuration);
+        }
+        
+        let config = Self::token_config(env, token)?;
+        if price < config.min_deposit {
+            return Err(StreamError::InvalidAmount);
+        }
+        if period_seconds < config.min_duration {
+            return Err(StreamError::InvalidDuration);
+        }
+        Self::check_max_deposit(&config, price)
+    }
+    
+    /// Helper: Check a stream total against the token's deposit cap
+    fn check_max_deposit(config: &TokenConfig, total: i128) -> Result<(), StreamError> {
+        match config.max_deposit {
+            Some(max) if total > max => Err(StreamError::InvalidAmount),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Helper: Check that a renewal allowance is usable
+    fn validate_allowance(auto_renew: bool, allowance: &Option<RenewalAllowance>) -> Result<(), StreamError> {
+        if let Some(allowance) = allowance {
+            if !auto_renew || allowance.remaining_cycles == 0 || allowance.max_amount_per_cycle <= 0 {
+                return Err(StreamError::InvalidAllowance);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Helper: Total amount a renewal allowance can still pull
+    fn allowance_budget(allowance: &RenewalAllowance) -> Result<i128, StreamError> {
+        allowance.max_amount_per_cycle
+            .checked_mul(allowance.remaining_cycles as i128)
+            .ok_or(StreamError::Overflow)
+    }
+    
+    /// Helper: Raise or lower the subscriber's token approval for this contract
+    /// 
+    /// Approvals are shared by every stream the subscriber holds in the same
+    /// token, so budgets are added to / removed from the current allowance
+    /// rather than overwriting it.
+    fn adjust_renewal_approval(
+        env: &Env,
+        subscriber: &Address,
+        token: &Address,
+        delta: i128,
+    ) -> Result<(), StreamError> {
+        let token_client = TokenClient::new(env, token);
+        let contract = env.current_contract_address();
+        
+        let current = token_client.allowance(subscriber, &contract);
+        let approved = current.checked_add(delta)
+            .ok_or(StreamError::Overflow)?
+            .max(0);
+        
+        token_client.approve(subscriber, &contract, &approved, &Self::approval_live_until(env, approved));
+        Ok(())
+    }
+    
+    /// Helper: Lower the subscriber's approval by a closing stream's unused renewal budget
+    /// 
+    /// Lowering an approval needs the subscriber's signature. When a keeper
+    /// closes the stream without it, the approval stays until the subscriber
+    /// next adjusts it; no stream can draw on it any more.
+    fn release_renewal_budget(env: &Env, stream: &mut Stream) -> Result<(), StreamError> {
+        let budget = Self::allowance_budget(&stream.renewal_allowance)?;
+        stream.renewal_allowance.remaining_cycles = 0;
+        if budget == 0 {
+            return Ok(());
+        }
+        
+        let token_client = TokenClient::new(env, &stream.token);
+        let contract = env.current_contract_address();
+        let approved = token_client.allowance(&stream.subscriber, &contract)
+            .saturating_sub(budget)
+            .max(0);
+        let live_until = Self::approval_live_until(env, approved);
+        let _ = token_client.try_approve(&stream.subscriber, &contract, &approved, &live_until);
+        Ok(())
+    }
+    
+    /// Helper: Ledger an approval of `approved` should live until
+    /// 
+    /// Keeps the approval alive as long as the network allows (the token
+    /// counts the current ledger too); an expired approval simply makes
+    /// keeper renewals fail until it is refreshed.
+    fn approval_live_until(env: &Env, approved: i128) -> u32 {
+        let sequence = env.ledger().sequence();
+        if approved > 0 {
+            sequence.saturating_add(env.storage().max_ttl().saturating_sub(1))
+        } else {
+            sequence
+        }
+    }
+    
+    /// Helper: Total amount the creator has earned on a stream at `now`
+    /// 
+    /// Whatever was accrued at the checkpoint is kept; the rest of the deposit
+    /// is released per the schedule as `unaccrued * elapsed / remaining` so the
+    /// creator receives exactly `total_amount` at `end_time` with no rounding
+    /// dust left behind. Stepped schedules measure time in whole steps from
+    /// `accrual_start`; front-loaded ones release their share up front on the
+    /// first stretch only.
+    fn accrued_amount(stream: &Stream, now: u64) -> Result<i128, StreamError> {
+        // Nothing accrues while paused; the pause began with a checkpoint
+        if stream.status == StreamStatus::Paused {
+            return Ok(stream.accrued);
+        }
+        if now >= stream.end_time {
+            return Ok(stream.total_amount);
+        }
+        if now <= stream.checkpoint_time {
+            return Ok(stream.accrued);
+        }
+        
+        let unaccrued = stream.total_amount
+            .checked_sub(stream.accrued)
+            .ok_or(StreamError::Overflow)?;
+        let streamed = match stream.schedule {
+            ReleaseSchedule::Linear => Self::pro_rata(
+                unaccrued,
+                now - stream.checkpoint_time,
+                stream.end_time - stream.checkpoint_time,
+            )?,
+            ReleaseSchedule::Stepped(interval) => {
+                // The checkpoint already released everything up to its step's end
+                let released_to = Self::step_end(stream, stream.checkpoint_time, interval);
+                if released_to >= stream.end_time {
+                    return Ok(stream.total_amount);
+                }
+                let reached = Self::step_end(stream, now, interval);
+                Self::pro_rata(unaccrued, reached - released_to, stream.end_time - released_to)?
+            }
+            ReleaseSchedule::FrontLoaded(bps) => {
+                let upfront = if stream.checkpoint_time <= stream.accrual_start {
+                    Self::pro_rata(unaccrued, bps as u64, BPS_DENOMINATOR as u64)?
+                } else {
+                    0
+                };
+                let linear = Self::pro_rata(
+                    unaccrued - upfront,
+                    now - stream.checkpoint_time,
+                    stream.end_time - stream.checkpoint_time,
+                )?;
+                upfront + linear
+            }
+        };
+        
+        stream.accrued.checked_add(streamed).ok_or(StreamError::Overflow)
+    }
+    
+    /// Helper: `amount * part / whole`, rounded down
+    fn pro_rata(amount: i128, part: u64, whole: u64) -> Result<i128, StreamError> {
+        amount
+            .checked_mul(part as i128)
+            .ok_or(StreamError::Overflow)?
+            .checked_div(whole as i128)
+            .ok_or(StreamError::Overflow)
+    }
+    
+    /// Helper: End of the schedule step running at `time` (capped at the stream's end)
+    /// 
+    /// A step is released as soon as it starts, so time counts as having
+    /// reached the end of the current step.
+    fn step_end(stream: &Stream, time: u64, interval: u64) -> u64 {
+        if time <= stream.accrual_start {
+            return stream.accrual_start;
+        }
+        let steps = (time - stream.accrual_start).div_ceil(interval);
+        stream.accrual_start
+            .saturating_add(steps.saturating_mul(interval))
+            .min(stream.end_time)
+    }
+    
+    /// Helper: Check a release schedule fits a stream of `duration_seconds`
+    fn validate_schedule(schedule: &ReleaseSchedule, duration_seconds: u64) -> Result<(), StreamError> {
+        let valid = match *schedule {
+            ReleaseSchedule::Linear => true,
+            ReleaseSchedule::Stepped(interval) => interval > 0 && interval <= duration_seconds,
+            ReleaseSchedule::FrontLoaded(bps) => bps as i128 <= BPS_DENOMINATOR,
+        };
+        if !valid {
+            return Err(StreamError::InvalidSchedule);
+        }
+        Ok(())
+    }
+    
+    /// Helper: Settle accrual up to `now` before the stream's terms change
+    fn checkpoint(stream: &mut Stream, now: u64) -> Result<(), StreamError> {
+        stream.accrued = Self::accrued_amount(stream, now)?;
+        stream.checkpoint_time = stream.checkpoint_time.max(now);
+        Ok(())
+    }
+    
+    /// Helper: Whether payouts are still held back by the stream's cliff
+    /// 
+    /// Time stands still for a paused stream, so a cliff still running when
+    /// the pause began holds until it has run out after the resume.
+    fn in_cliff(stream: &Stream, now: u64) -> bool {
+        let now = if stream.status == StreamStatus::Paused { stream.paused_at } else { now };
+        now < stream.cliff_end
+    }
+    
+    /// Helper: Lift a pause at `resumed_at`, pushing the schedule back by its length
+    fn resume(stream: &mut Stream, resumed_at: u64) -> u64 {
+        let paused_for = resumed_at.saturating_sub(stream.paused_at);
+        stream.end_time = stream.end_time.saturating_add(paused_for);
+        stream.checkpoint_time = stream.checkpoint_time.saturating_add(paused_for);
+        stream.accrual_start = stream.accrual_start.saturating_add(paused_for);
+        // A trial or cliff still running when the pause began is pushed back too
+        if stream.trial_end > stream.paused_at {
+            stream.trial_end = stream.trial_end.saturating_add(paused_for);
+        }
+        if stream.cliff_end > stream.paused_at {
+            stream.cliff_end = stream.cliff_end.saturating_add(paused_for);
+        }
+        stream.paused_at = 0;
+        stream.status = StreamStatus::Active;
+        paused_for
+    }
+    
+    /// Helper: Displayed per-second rate from the checkpoint to the end
+    fn forward_rate(stream: &Stream) -> Result<i128, StreamError> {
+        let remaining = stream.end_time.saturating_sub(stream.checkpoint_time).max(1);
+        stream.total_amount
+            .saturating_sub(stream.accrued)
+            .checked_div(remaining as i128)
+            .ok_or(StreamError::Overflow)
+    }
+    
+    /// Helper: Platform fee owed on a gross payout from a stream
+    fn platform_fee(env: &Env, stream: &Stream, amount: i128) -> Result<i128, StreamError> {
+        let fee_bps = Self::applied_fee_bps(env, stream);
+        amount
+            .checked_mul(fee_bps)
+            .ok_or(StreamError::Overflow)?
+            .checked_div(BPS_DENOMINATOR)
+            .ok_or(StreamError::Overflow)
+    }
+    
+    /// Helper: Fee rate a stream's next payout is charged (what events report)
+    fn applied_fee_bps(env: &Env, stream: &Stream) -> i128 {
+        Self::resolve_fee_bps(env, &stream.creator, &stream.token, Some(stream.fee_bps))
+    }
+    
+    /// Helper: Fee policy - platform fee in basis points for a creator's payouts in a token
+    /// 
+    /// New streams lock in the creator's override, or the global fee without
+    /// one (`snapshot` is None). Payouts start from the stream's locked-in fee
+    /// (`snapshot`). Either way a volume tier the creator has reached lowers
+    /// the fee at once; nothing here ever raises it for a running stream.
+    fn resolve_fee_bps(env: &Env, creator: &Address, token: &Address, snapshot: Option<i128>) -> i128 {
+        let base = snapshot.unwrap_or_else(|| {
+            let key = DataKey::CreatorFeeOverride(creator.clone());
+            Self::bump_persistent(env, &key, STREAM_TTL_BUFFER);
+            env.storage().persistent().get(&key)
+                .unwrap_or_else(|| Self::get_platform_fee_bps(env))
+        });
+        
+        let tiers: Vec<VolumeFeeTier> = env.storage().instance().get(&DataKey::VolumeFeeTiers(token.clone()))
+            .unwrap_or(Vec::new(env));
+        if tiers.is_empty() {
+            return base;
+        }
+        let volume: i128 = env.storage().persistent()
+            .get(&DataKey::CreatorVolume(creator.clone(), token.clone()))
+            .unwrap_or(0);
+        
+        // Tiers are sorted by threshold, so the last one reached applies
+        let mut fee_bps = base;
+        for tier in tiers.iter() {
+            if volume < tier.min_volume {
+                break;
+            }
+            fee_bps = base.min(tier.fee_bps);
+        }
+        fee_bps
+    }
+    
+    /// Helper: Get platform fee in basis points
+    fn get_platform_fee_bps(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKey::PlatformFeeBps)
+            .unwrap_or(DEFAULT_PLATFORM_FEE_BPS)
+    }
+    
+    /// Helper: Get grace period in seconds
+    fn get_grace_period(env: &Env) -> u64 {
+        env.storage().instance().get(&DataKey::GracePeriod)
+            .unwrap_or(DEFAULT_GRACE_PERIOD)
+    }
+    
+    /// Helper: Increment or decrement active subscriber count for a creator
+    fn increment_subscriber_count(env: &Env, creator: &Address, increment: bool) {
+        let key = DataKey::ActiveSubscriberCount(creator.clone());
+        let current: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+        let new_count = if increment {
+            current.saturating_add(1)
+        } else {
+            current.saturating_sub(1)
+        };
+        env.storage().persistent().set(&key, &new_count);
+    }
+    
+    // === Subscriber Functions ===
+    
+    /// Extend an existing stream with additional time and funds
+    /// 
+    /// Only running streams can be extended; once a stream has ended it has
+    /// to be renewed or replaced instead.
+    /// 
+    /// # Arguments
+    /// * `stream_id` - The stream to extend
+    /// * `additional_amount` - Additional tokens to deposit
+    /// * `additional_seconds` - Additional time to add
+    pub fn extend_stream(
+        env: Env,
+        stream_id: u64,
+        additional_amount: i128,
+        additional_seconds: u64,
+    ) -> Result<(), StreamError> {
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Only subscriber can extend
+        stream.subscriber.require_auth();
+        
+        Self::require_not_paused(&env)?;
+        
+        // An ended stream would hand the extension over at once
+        let now = env.ledger().timestamp();
+        if stream.status != StreamStatus::Active || now >= stream.end_time {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        if additional_amount <= 0 {
+            return Err(StreamError::InvalidAmount);
+        }
+        if additional_seconds == 0 {
+            return Err(StreamError::InvalidDuration);
+        }
+        
+        // Token must still be allowlisted and the new total within its cap
+        let config = Self::token_config(&env, &stream.token)?;
+        let new_total = stream.total_amount.checked_add(additional_amount)
+            .ok_or(StreamError::Overflow)?;
+        Self::check_max_deposit(&config, new_total)?;
+        
+        // Tiered streams can only be extended at the tier's current rate
+        if stream.tier_id != 0 {
+            let tier = Self::active_tier(&env, &stream.creator, stream.tier_id)?;
+            let paid = additional_amount.checked_mul(tier.period_seconds as i128)
+                .ok_or(StreamError::Overflow)?;
+            let priced = tier.price.checked_mul(additional_seconds as i128)
+                .ok_or(StreamError::Overflow)?;
+            if paid != priced {
+                return Err(StreamError::TierMismatch);
+            }
+        }
+        
+        // Transfer additional tokens
+        let token_client = TokenClient::new(&env, &stream.token);
+        token_client.transfer(&stream.subscriber, &env.current_contract_address(), &additional_amount);
+        
+        // Settle accrual so far; the new funds only stream from now on
+        Self::checkpoint(&mut stream, now)?;
+        
+        // Update stream
+        stream.total_amount = new_total;
+        stream.cycle_amount = stream.cycle_amount.saturating_add(additional_amount);
+        stream.end_time += additional_seconds;
+        stream.duration_seconds += additional_seconds;
+        
+        // Refresh the displayed rate for the remainder of the stream
+        stream.rate_per_second = Self::forward_rate(&stream)?;
+        
+        Self::save_stream(&env, &stream);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "stream_extended"), stream.subscriber.clone()),
+            (stream_id, additional_amount, additional_seconds),
+        );
+        
+        Ok(())
+    }
+    
+    /// Move a stream to another tier (or custom terms) without closing it
+    /// 
+    /// Accrual so far is locked in for the creator and the unstreamed balance
+    /// is credited towards `new_amount`; only the difference is pulled from the
+    /// subscriber, or any excess refunded. The new terms run from now for
+    /// `new_duration` seconds. Fee snapshot, release schedule (which must fit
+    /// `new_duration`) and renewal settings carry over.
+    /// 
+    /// # Returns
+    /// Net amount charged to the subscriber (negative when refunded)
+    pub fn change_tier(
+        env: Env,
+        stream_id: u64,
+        new_tier_id: u32,
+        new_amount: i128,
+        new_duration: u64,
+    ) -> Result<i128, StreamError> {
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Only subscriber can change tier
+        stream.subscriber.require_auth();
+        
+        Self::require_not_paused(&env)?;
+        
+        let now = env.ledger().timestamp();
+        if stream.status != StreamStatus::Active || now >= stream.end_time {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        if new_amount <= 0 {
+            return Err(StreamError::InvalidAmount);
+        }
+        if new_duration == 0 {
+            return Err(StreamError::InvalidDuration);
+        }
+        // The release schedule carries over, so it must fit the new duration
+        Self::validate_schedule(&stream.schedule, new_duration)?;
+        
+        // New terms must respect the token limits and the catalogue
+        let config = Self::token_config(&env, &stream.token)?;
+        if new_amount < config.min_deposit {
+            return Err(StreamError::InvalidAmount);
+        }
+        if new_duration < config.min_duration {
+            return Err(StreamError::InvalidDuration);
+        }
+        Self::check_tier_terms(&env, &stream.creator, new_tier_id, &stream.token, new_amount, new_duration)?;
+        
+        // Only one active stream per tier and token
+        let old_key = Self::active_stream_key(&stream);
+        let new_key = DataKey::ActiveStream(
+            stream.subscriber.clone(),
+            stream.creator.clone(),
+            new_tier_id,
+            stream.token.clone(),
+        );
+        if new_tier_id != stream.tier_id && env.storage().persistent().has(&new_key) {
+            return Err(StreamError::StreamAlreadyExists);
+        }
+        
+        // Lock in accrual so far; what hasn't streamed yet is the credit
+        Self::checkpoint(&mut stream, now)?;
+        let credit = stream.total_amount.saturating_sub(stream.accrued);
+        
+        let new_total = stream.accrued.checked_add(new_amount)
+            .ok_or(StreamError::Overflow)?;
+        Self::check_max_deposit(&config, new_total)?;
+        
+        // Settle the difference with the subscriber
+        let charge = new_amount.saturating_sub(credit);
+        let token_client = TokenClient::new(&env, &stream.token);
+        let contract = env.current_contract_address();
+        if charge > 0 {
+            token_client.transfer(&stream.subscriber, &contract, &charge);
+        } else if charge < 0 {
+            token_client.transfer(&contract, &stream.subscriber, &(-charge));
+        }
+        
+        // Re-key the active mapping if the tier changed
+        let old_tier_id = stream.tier_id;
+        if new_tier_id != old_tier_id {
+            env.storage().persistent().remove(&old_key);
+            env.storage().persistent().set(&new_key, &stream_id);
+            Self::adjust_tier_count(&env, &stream, old_tier_id, false);
+            Self::adjust_tier_count(&env, &stream, new_tier_id, true);
+        }
+        
+        // Update stream
+        stream.tier_id = new_tier_id;
+        stream.total_amount = new_total;
+        // New terms start once any trial still running is over
+        stream.end_time = stream.checkpoint_time.saturating_add(new_duration);
+        // A cliff never outlasts the stream, as when it was opened
+        stream.cliff_end = stream.cliff_end.min(stream.end_time);
+        stream.duration_seconds = new_duration;
+        stream.cycle_amount = new_amount;
+        stream.rate_per_second = Self::forward_rate(&stream)?;
+        
+        Self::save_stream(&env, &stream);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "tier_changed"), stream.subscriber.clone()),
+            (stream_id, old_tier_id, new_tier_id, credit, charge),
+        );
+        
+        Ok(charge)
+    }
+    
+    /// Put a stream on hold (e.g. while travelling)
+    /// 
+    /// Accrual stops until the stream is resumed, and the end time then moves
+    /// out by however long it was paused. The creator's pause policy caps how
+    /// often streams may pause; a pause that outlasts the policy's maximum
+    /// length resumes by itself once the limit is reached.
+    pub fn pause_stream(env: Env, stream_id: u64) -> Result<(), StreamError> {
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Only subscriber can pause
+        stream.subscriber.require_auth();
+        
+        let now = env.ledger().timestamp();
+        if stream.status != StreamStatus::Active || now >= stream.end_time {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        // Enforce the creator's limits, starting a new window if the last one is over
+        let policy = Self::get_pause_policy(env.clone(), stream.creator.clone());
+        let window_over = policy.period_seconds > 0
+            && now >= stream.pause_window_start.saturating_add(policy.period_seconds);
+        if stream.pause_count == 0 || window_over {
+            stream.pause_count = 0;
+            stream.pause_window_start = now;
+        }
+        if policy.max_pauses > 0 && stream.pause_count >= policy.max_pauses {
+            return Err(StreamError::PauseLimitReached);
+        }
+        
+        // Lock in accrual so far, then stop the clock
+        Self::checkpoint(&mut stream, now)?;
+        stream.status = StreamStatus::Paused;
+        stream.paused_at = now;
+        stream.pause_limit = policy.max_pause_seconds;
+        stream.pause_count += 1;
+        
+        Self::save_stream(&env, &stream);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "stream_paused"), stream.subscriber.clone()),
+            (stream_id, stream.pause_limit, stream.pause_count),
+        );
+        
+        Ok(())
+    }
+    
+    /// Resume a paused stream
+    /// 
+    /// # Returns
+    /// The stream's new end time
+    pub fn resume_stream(env: Env, stream_id: u64) -> Result<u64, StreamError> {
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Only subscriber can resume
+        stream.subscriber.require_auth();
+        
+        if stream.status != StreamStatus::Paused {
+            return Err(StreamError::StreamNotPaused);
+        }
+        
+        let paused_for = Self::resume(&mut stream, env.ledger().timestamp());
+        Self::save_stream(&env, &stream);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "stream_resumed"), stream.subscriber.clone()),
+            (stream_id, paused_for, stream.end_time),
+        );
+        
+        Ok(stream.end_time)
+    }
+    
+    /// Toggle auto-renewal for a stream
+    /// 
+    /// # Arguments
+    /// * `stream_id` - The stream to update
+    /// * `enabled` - Whether auto-renewal should be on
+    /// * `renewal_allowance` - Budget keepers may renew against (replaces any previous one)
+    pub fn toggle_auto_renew(
+        env: Env,
+        stream_id: u64,
+        enabled: bool,
+        renewal_allowance: Option<RenewalAllowance>,
+    ) -> Result<(), StreamError> {
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Only subscriber can toggle
+        stream.subscriber.require_auth();
+        
+        if stream.status != StreamStatus::Active {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        Self::validate_allowance(enabled, &renewal_allowance)?;
+        
+        // Swap the old budget for the new one in the token approval
+        let old_budget = Self::allowance_budget(&stream.renewal_allowance)?;
+        let new_budget = match &renewal_allowance {
+            Some(allowance) => Self::allowance_budget(allowance)?,
+            None => 0,
+        };
+        if old_budget != new_budget {
+            Self::adjust_renewal_approval(&env, &stream.subscriber, &stream.token, new_budget - old_budget)?;
+        }
+        
+        stream.auto_renew = enabled;
+        stream.renewal_allowance = renewal_allowance.unwrap_or(RenewalAllowance {
+            remaining_cycles: 0,
+            max_amount_per_cycle: 0,
+        });
+        Self::save_stream(&env, &stream);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "auto_renew_toggled"), stream.subscriber.clone()),
+            (stream_id, enabled, stream.renewal_allowance.remaining_cycles),
+        );
+        
+        Ok(())
+    }
+    
+    /// Renew an expired or expiring stream (for auto-renewal)
+    /// 
+    /// Any external keeper can call this while the stream has keeper renewals
+    /// left in its allowance and the renewal amount fits the per-cycle cap.
+    /// Otherwise the subscriber must authorize the renewal.
+    pub fn renew_stream(env: Env, stream_id: u64) -> Result<u64, StreamError> {
+        Self::require_not_paused(&env)?;
+        
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Check if stream is eligible for renewal
+        let now = env.ledger().timestamp();
+        let grace_period = Self::get_grace_period(&env);
+        
+        // Cancelled, terminated or paused streams must never be revived by a keeper
+        if stream.status != StreamStatus::Active && stream.status != StreamStatus::Completed {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        // Can only renew if stream has ended or is within grace period
+        if stream.status == StreamStatus::Active && now < stream.end_time {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        // Must have auto-renew enabled
+        if !stream.auto_renew {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        // Check if within grace period (not too late); lapsed streams are
+        // settled through `expire_stream` instead
+        if now > stream.end_time + grace_period {
+            return Err(StreamError::InGracePeriod);
+        }
+        
+        // Tiered streams renew at the current catalogue terms, custom
+        // streams for another period on the terms they last ran on
+        let (amount, duration_seconds) = if stream.tier_id != 0 {
+            let tier = Self::active_tier(&env, &stream.creator, stream.tier_id)?;
+            (tier.price, tier.period_seconds)
+        } else {
+            (stream.cycle_amount, stream.duration_seconds)
+        };
+        
+        // Keepers may renew within the allowance; anything else needs the subscriber
+        let mut allowance = stream.renewal_allowance.clone();
+        let keeper_funded = allowance.remaining_cycles > 0 && amount <= allowance.max_amount_per_cycle;
+        if keeper_funded {
+            allowance.remaining_cycles -= 1;
+        } else {
+            stream.subscriber.require_auth();
+        }
+        
+        // Retire the old stream so its successor can take the active slot
+        if stream.status == StreamStatus::Active {
+            Self::deactivate(&env, &stream);
+        }
+        stream.status = StreamStatus::Completed;
+        stream.auto_renew = false;
+        stream.renewal_allowance.remaining_cycles = 0;
+        Self::save_stream(&env, &stream);
+        
+        // Create new stream with same parameters
+        let new_stream_id = Self::open_stream(
+            &env,
+            &stream.subscriber,
+            &stream.creator,
+            &stream.token,
+            amount,
+            duration_seconds,
+            stream.tier_id,
+            true, // Keep auto-renew on
+            Some(allowance),
+            // Trials and cliffs only apply to the first period
+            &StreamOptions {
+                schedule: stream.schedule.clone(),
+                referrer: stream.referrer.clone(),
+                ..StreamOptions::default()
+            },
+        )?;
+        
+        // Fund the new stream
+        let token_client = TokenClient::new(&env, &stream.token);
+        let contract = env.current_contract_address();
+        if keeper_funded {
+            // Pull against the subscriber's approval; a missing approval or
+            // balance fails the renewal instead of trapping
+            let pulled = token_client.try_transfer_from(&contract, &stream.subscriber, &contract, &amount);
+            if !matches!(pulled, Ok(Ok(()))) {
+                return Err(StreamError::RenewalFailed);
+            }
+        } else {
+            token_client.transfer(&stream.subscriber, &contract, &amount);
+        }
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "stream_renewed"), stream.subscriber.clone()),
+            (stream_id, new_stream_id, keeper_funded),
+        );
+        
+        Ok(new_stream_id)
+    }
+    
+    // === Keeper Functions ===
+    
+    /// Settle a stream that has run its course (anyone can call)
+    /// 
+    /// Pays the creator whatever is left unwithdrawn (minus fee), marks the
+    /// stream completed and frees its active slot. Auto-renewing streams can
+    /// only be expired once their grace period has passed; one already
+    /// completed by a final withdrawal only has its renewal budget released.
+    /// 
+    /// # Returns
+    /// Amount paid to the creator (after platform fee)
+    pub fn expire_stream(env: Env, stream_id: u64) -> Result<i128, StreamError> {
+        Self::require_not_paused(&env)?;
+        
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Fully withdrawn streams stay renewable until their grace period lapses
+        let withdrawn_out = stream.status == StreamStatus::Completed && stream.auto_renew;
+        if stream.status != StreamStatus::Active && !withdrawn_out {
+            return Err(StreamError::StreamNotActive);
+        }
+        
+        // Leave renewable streams alone while they can still be renewed
+        let now = env.ledger().timestamp();
+        let mut expires_at = stream.end_time;
+        if stream.auto_renew {
+            expires_at = expires_at.saturating_add(Self::get_grace_period(&env));
+        }
+        if now < expires_at {
+            return Err(StreamError::StreamNotEnded);
+        }
+        
+        stream.auto_renew = false;
+        let (creator_amount, fee, destination) = if withdrawn_out {
+            // Nothing left to pay and already out of the active set
+            Self::release_renewal_budget(&env, &mut stream)?;
+            Self::save_stream(&env, &stream);
+            (0, 0, Self::payout_address(&env, &stream.creator))
+        } else {
+            let (creator_amount, fee, _, destination) =
+                Self::close_stream(&env, &mut stream, StreamStatus::Completed, true)?;
+            (creator_amount, fee, destination)
+        };
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "stream_expired"), stream.creator.clone()),
+            (stream_id, creator_amount, fee, destination),
+        );
+        
+        Ok(creator_amount)
+    }
+    
+    /// Expire every ended stream in `stream_ids` (anyone can call)
+    /// 
+    /// Streams that cannot be expired yet are skipped.
+    /// 
+    /// # Returns
+    /// Number of streams expired
+    pub fn sweep_expired(env: Env, stream_ids: Vec<u64>) -> Result<u32, StreamError> {
+        Self::require_not_paused(&env)?;
+        
+        let mut expired: u32 = 0;
+        for id in stream_ids.iter() {
+            if Self::expire_stream(env.clone(), id).is_ok() {
+                expired += 1;
+            }
+        }
+        
+        Ok(expired)
+    }
+    
+    // === Creator Functions ===
+    
+    /// Creator terminates a stream
+    /// Use this to remove problematic subscribers
+    /// 
+    /// # Arguments
+    /// * `stream_id` - The stream to terminate
+    /// * `mode` - Settle accrued earnings, or refund everything as goodwill
+    ///   (only refunds are possible while the contract is paused)
+    /// * `reason` - Creator-defined reason code recorded on the stream
+    /// 
+    /// # Returns
+    /// (creator_received, subscriber_refunded)
+    pub fn terminate_stream(
+        env: Env,
+        stream_id: u64,
+        mode: TerminationMode,
+        reason: u32,
+    ) -> Result<(i128, i128), StreamError> {
+        let mut stream = Self::load_stream(&env, stream_id)?;
+        
+        // Only creator can terminate
+        stream.creator.require_auth();
+        
+        // Settling pays the creator, which the circuit breaker holds back
+        if mode == TerminationMode::Settle {
+            Self::require_not_paused(&env)?;
+        }
+        
+        if stream.status != StreamStatus::Active && stream.status != StreamStatus::Paused {
+            return Err(StreamError::AlreadyTerminated);
+        }
+        
+        // Pending earnings the creator gives up in full-refund mode
+        let earned = Self::accrued_amount(&stream, env.ledger().timestamp())?;
+        let pending = earned.saturating_sub(stream.withdrawn);
+        
+        // Nothing is payable within the cliff, whatever the mode
+        stream.termination_reason = reason;
+        let settle = mode == TerminationMode::Settle && !Self::in_cliff(&stream, env.ledger().timestamp());
+        let fee_bps = Self::applied_fee_bps(&env, &stream);
+        let (creator_amount, fee, subscriber_refund, destination) =
+            Self::close_stream(&env, &mut stream, StreamStatus::Terminated, settle)?;
+        let forfeited = if settle { 0 } else { pending };
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "stream_terminated"), stream.creator.clone()),
+            (stream_id, mode, reason, creator_amount, fee, fee_bps, subscriber_refund, forfeited, destination),
+        );
+        
+        Ok((creator_amount + fee, subscriber_refund))
+    }
+    
+    /// Creator withdraws from a page of their active streams at once
+    /// 
+    /// Streams that complete leave the active set and its last entry moves
+    /// into their slot, so walk the pages from the end of the set back to 0
+    /// to reach every stream exactly once.
+    /// 
+    /// # Arguments
+    /// * `creator` - The creator withdrawing
+    /// * `start` - Index into the creator's active set to start from
+    /// * `limit` - Number of streams to visit, capped at `MAX_PAGE_SIZE`
+    /// 
+    /// # Returns
+    /// Total amount withdrawn (after fees)
+    pub fn withdraw_all(env: Env, creator: Address, start: u32, limit: u32) -> Result<i128, StreamError> {
+        creator.require_auth();
+        
+        Self::require_not_paused(&env)?;
+        
+        let stream_ids = Self::active_page(&env, &creator, false, start, limit);
+        Ok(Self::withdraw_each(&env, &creator, &stream_ids))
+    }
+    
+    /// Creator withdraws from a page of their stream history at once
+    /// 
+    /// # Arguments
+    /// * `creator` - The creator withdrawing
+    /// * `start` - Index into the creator's history to start from
+    /// * `limit` - Number of streams to visit, capped at `MAX_PAGE_SIZE`
+    /// 
+    /// # Returns
+    /// Total amount withdrawn (after fees)
+    pub fn withdraw_batch(env: Env, creator: Address, start: u32, limit: u32) -> Result<i128, StreamError> {
+        creator.require_auth();
+        
+        Self::require_not_paused(&env)?;
+        
+        let stream_ids = Self::get_creator_streams(env.clone(), creator.clone(), start, limit);
+        Ok(Self::withdraw_each(&env, &creator, &stream_ids))
+    }
+    
+    /// Helper: Withdraw from each of a creator's streams, skipping any that fail
+    fn withdraw_each(env: &Env, creator: &Address, stream_ids: &Vec<u64>) -> i128 {
+        let mut total_withdrawn: i128 = 0;
+        
+        for id in stream_ids.iter() {
+            // Try to withdraw from each stream, ignore errors (stream might be inactive)
+            if let Ok(amount) = Self::withdraw(env.clone(), id) {
+                total_withdrawn = total_withdrawn.saturating_add(amount);
+            }
+        }
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(env, "batch_withdrawal"), creator.clone()),
+            (total_withdrawn, Self::payout_address(env, creator)),
+        );
+        
+        total_withdrawn
+    }
+    
+    /// Get the number of distinct subscribers with an active stream to a creator
+    pub fn get_active_subscriber_count(env: Env, creator: Address) -> u64 {
+        let key = DataKey::ActiveSubscriberCount(creator);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+    
+    /// Keep a stream, its index entries and the contract config alive
+    /// 
+    /// Permissionless so keepers can prevent long-running or auto-renewing
+    /// streams from being archived while they still hold escrowed funds.
+    pub fn bump_stream(env: Env, stream_id: u64) -> Result<(), StreamError> {
+        // Loading extends the stream's own entries and indexes
+        let stream = Self::load_stream(&env, stream_id)?;
+        Self::bump_creator_config(&env, &stream);
+        Ok(())
+    }
+    
+    /// Add a tier to the creator's catalogue
+    /// 
+    /// # Arguments
+    /// * `creator` - Creator offering the tier
+    /// * `token` - Allowlisted token the tier is priced in
+    /// * `price` - Amount charged per period
+    /// * `period_seconds` - Length of one period
+    /// 
+    /// # Returns
+    /// The new tier ID
+    pub fn create_tier(
+        env: Env,
+        creator: Address,
+        token: Address,
+        price: i128,
+        period_seconds: u64,
+    ) -> Result<u32, StreamError> {
+        creator.require_auth();
+        
+        Self::validate_tier_terms(&env, &token, price, period_seconds)?;
+        
+        let id_key = DataKey::NextTierId(creator.clone());
+        let tier_id: u32 = env.storage().persistent().get(&id_key).unwrap_or(1);
+        env.storage().persistent().set(&id_key, &(tier_id + 1));
+        Self::bump_persistent(&env, &id_key, STREAM_TTL_BUFFER);
+        
+        let tier = Tier { id: tier_id, token, price, period_seconds, active: true };
+        let key = DataKey::Tier(creator.clone(), tier_id);
+        env.storage().persistent().set(&key, &tier);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        
+        let list_key = DataKey::CreatorTiers(creator.clone());
+        let mut tiers: Vec<u32> = env.storage().persistent().get(&list_key)
+            .unwrap_or(Vec::new(&env));
+        tiers.push_back(tier_id);
+        env.storage().persistent().set(&list_key, &tiers);
+        Self::bump_persistent(&env, &list_key, STREAM_TTL_BUFFER);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "tier_created"), creator),
+            (tier_id, price, period_seconds),
+        );
+        
+        Ok(tier_id)
+    }
+    
+    /// Update a tier's pricing or availability
+    /// 
+    /// Running streams keep the terms they were bought at; the new terms
+    /// apply to new subscriptions, extensions and renewals.
+    pub fn update_tier(
+        env: Env,
+        creator: Address,
+        tier_id: u32,
+        price: i128,
+        period_seconds: u64,
+        active: bool,
+    ) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        let mut tier = Self::load_tier(&env, &creator, tier_id)?;
+        Self::validate_tier_terms(&env, &tier.token, price, period_seconds)?;
+        
+        tier.price = price;
+        tier.period_seconds = period_seconds;
+        tier.active = active;
+        env.storage().persistent().set(&DataKey::Tier(creator.clone(), tier_id), &tier);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "tier_updated"), creator),
+            (tier_id, price, period_seconds, active),
+        );
+        
+        Ok(())
+    }
+    
+    /// Remove a tier from the creator's catalogue
+    /// 
+    /// Running streams on the tier continue until they end but can no
+    /// longer be extended or renewed. Tier IDs are never reused.
+    pub fn remove_tier(env: Env, creator: Address, tier_id: u32) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        let key = DataKey::Tier(creator.clone(), tier_id);
+        if !env.storage().persistent().has(&key) {
+            return Err(StreamError::TierNotFound);
+        }
+        env.storage().persistent().remove(&key);
+        
+        let list_key = DataKey::CreatorTiers(creator.clone());
+        let mut tiers: Vec<u32> = env.storage().persistent().get(&list_key)
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = tiers.first_index_of(tier_id) {
+            tiers.remove(index);
+        }
+        env.storage().persistent().set(&list_key, &tiers);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "tier_removed"), creator),
+            tier_id,
+        );
+        
+        Ok(())
+    }
+    
+    /// Get a tier from a creator's catalogue
+    pub fn get_tier(env: Env, creator: Address, tier_id: u32) -> Result<Tier, StreamError> {
+        Self::load_tier(&env, &creator, tier_id)
+    }
+    
+    /// Get every tier in a creator's catalogue, including inactive ones
+    pub fn get_creator_tiers(env: Env, creator: Address) -> Vec<Tier> {
+        let list_key = DataKey::CreatorTiers(creator.clone());
+        let ids: Vec<u32> = env.storage().persistent().get(&list_key)
+            .unwrap_or(Vec::new(&env));
+        Self::bump_persistent(&env, &list_key, STREAM_TTL_BUFFER);
+        
+        let mut tiers = Vec::new(&env);
+        for tier_id in ids.iter() {
+            if let Ok(tier) = Self::load_tier(&env, &creator, tier_id) {
+                tiers.push_back(tier);
+            }
+        }
+        tiers
+    }
+    
+    /// Set the limits on subscribers pausing the creator's streams
+    /// 
+    /// Applies from each stream's next pause; a pause already running keeps
+    /// the maximum length it started with.
+    pub fn set_pause_policy(env: Env, creator: Address, policy: PausePolicy) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        let key = DataKey::PausePolicy(creator.clone());
+        env.storage().persistent().set(&key, &policy);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "pause_policy_set"), creator),
+            (policy.max_pause_seconds, policy.max_pauses, policy.period_seconds),
+        );
+        
+        Ok(())
+    }
+    
+    /// Get a creator's pause limits (no limits if none were set)
+    pub fn get_pause_policy(env: Env, creator: Address) -> PausePolicy {
+        let key = DataKey::PausePolicy(creator);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key).unwrap_or_default()
+    }
+    
+    /// Set the trial and cliff lengths the creator offers
+    /// 
+    /// Only checked when streams are opened; running streams keep their terms.
+    pub fn set_trial_policy(env: Env, creator: Address, policy: TrialPolicy) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        let key = DataKey::TrialPolicy(creator.clone());
+        env.storage().persistent().set(&key, &policy);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "trial_policy_set"), creator),
+            (policy.max_trial_seconds, policy.max_cliff_seconds),
+        );
+        
+        Ok(())
+    }
+    
+    /// Get the trial and cliff lengths a creator offers (none if never set)
+    pub fn get_trial_policy(env: Env, creator: Address) -> TrialPolicy {
+        let key = DataKey::TrialPolicy(creator);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key).unwrap_or_default()
+    }
+    
+    /// Whether a subscriber has already had a trial with a creator
+    pub fn has_used_trial(env: Env, subscriber: Address, creator: Address) -> bool {
+        env.storage().persistent().has(&DataKey::TrialUsed(subscriber, creator))
+    }
+    
+    /// Send the creator's payouts to another address (e.g. a treasury)
+    /// 
+    /// Applies to every later withdrawal and settlement on the creator's
+    /// streams; the creator address itself still authorizes everything.
+    pub fn set_payout_address(env: Env, creator: Address, payout: Address) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        if payout == env.current_contract_address() {
+            return Err(StreamError::InvalidPayoutAddress);
+        }
+        
+        let key = DataKey::PayoutAddress(creator.clone());
+        env.storage().persistent().set(&key, &payout);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "payout_address_set"), creator),
+            payout,
+        );
+        
+        Ok(())
+    }
+    
+    /// Send the creator's payouts back to the creator address
+    pub fn clear_payout_address(env: Env, creator: Address) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        env.storage().persistent().remove(&DataKey::PayoutAddress(creator.clone()));
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "payout_address_cleared"), creator),
+            (),
+        );
+        
+        Ok(())
+    }
+    
+    /// Get where a creator's payouts are sent (the creator if none is registered)
+    pub fn get_payout_address(env: Env, creator: Address) -> Address {
+        Self::payout_address(&env, &creator)
+    }
+    
+    /// Offer referrers a share of the creator's revenue on streams they refer
+    /// 
+    /// Capped by the admin's referral limits. Applies to streams opened or
+    /// renewed from now on.
+    pub fn set_referral_share(env: Env, creator: Address, share_bps: u32) -> Result<(), StreamError> {
+        creator.require_auth();
+        
+        if share_bps > Self::get_referral_config(env.clone()).max_revenue_share_bps {
+            return Err(StreamError::InvalidReferral);
+        }
+        
+        let key = DataKey::CreatorReferralShare(creator.clone());
+        env.storage().persistent().set(&key, &share_bps);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "referral_share_set"), creator),
+            share_bps,
+        );
+        
+        Ok(())
+    }
+    
+    /// Get the share of revenue a creator offers referrers (0 if none)
+    pub fn get_creator_referral_share(env: Env, creator: Address) -> u32 {
+        let key = DataKey::CreatorReferralShare(creator);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+    
+    /// Get the total a referrer has been paid in a token
+    pub fn get_referral_earnings(env: Env, referrer: Address, token: Address) -> i128 {
+        let key = DataKey::ReferralEarnings(referrer, token);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+    
+    /// Split the creator's payouts among collaborators
+    /// 
+    /// Accrual so far on the creator's active streams is paid out under the
+    /// previous split first, a page at a time; call again with the same
+    /// shares until this returns 0, at which point the new split is in force.
+    /// Streams that have already ended and left the active set are paid
+    /// under whichever split is in place when they are withdrawn.
+    /// 
+    /// # Arguments
+    /// * `creator` - The creator whose payouts are split
+    /// * `shares` - Up to `MAX_SPLIT_RECIPIENTS` distinct recipients whose
+    ///   basis points sum to 10000
+    /// * `limit` - Number of streams to settle, capped at `MAX_PAGE_SIZE`
+    /// 
+    /// # Returns
+    /// Active streams still to settle before the split takes effect
+    pub fn set_revenue_split(env: Env, creator: Address, shares: Vec<SplitShare>, limit: u32) -> Result<u32, StreamError> {
+        creator.require_auth();
+        
+        Self::require_not_paused(&env)?;
+        
+        if shares.is_empty() || shares.len() > MAX_SPLIT_RECIPIENTS {
+            return Err(StreamError::InvalidSplit);
+        }
+        let contract = env.current_contract_address();
+        let mut total: i128 = 0;
+        for (index, share) in shares.iter().enumerate() {
+            if share.bps == 0 || share.recipient == contract {
+                return Err(StreamError::InvalidSplit);
+            }
+            // Recipients must be distinct
+            if shares.iter().skip(index + 1).any(|other| other.recipient == share.recipient) {
+                return Err(StreamError::InvalidSplit);
+            }
+            total += share.bps as i128;
+        }
+        if total != BPS_DENOMINATOR {
+            return Err(StreamError::InvalidSplit);
+        }
+        
+        let remaining = Self::stage_split(&env, &creator, shares.clone(), limit);
+        if remaining > 0 {
+            return Ok(remaining);
+        }
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "revenue_split_set"), creator),
+            shares,
+        );
+        
+        Ok(0)
+    }
+    
+    /// Stop splitting the creator's payouts
+    /// 
+    /// Accrual so far is paid out under the current split first, paged like
+    /// `set_revenue_split`; the split is removed once this returns 0.
+    /// 
+    /// # Arguments
+    /// * `creator` - The creator whose split is cleared
+    /// * `limit` - Number of streams to settle, capped at `MAX_PAGE_SIZE`
+    pub fn clear_revenue_split(env: Env, creator: Address, limit: u32) -> Result<u32, StreamError> {
+        creator.require_auth();
+        
+        Self::require_not_paused(&env)?;
+        
+        let remaining = Self::stage_split(&env, &creator, Vec::new(&env), limit);
+        if remaining > 0 {
+            return Ok(remaining);
+        }
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "revenue_split_cleared"), creator),
+            (),
+        );
+        
+        Ok(0)
+    }
+    
+    /// Get a creator's revenue split (empty when everything goes to the creator)
+    pub fn get_revenue_split(env: Env, creator: Address) -> Vec<SplitShare> {
+        Self::revenue_split(&env, &creator)
+    }
+    
+    // === Admin Functions ===
+    
+    /// Update platform wallet (admin only)
+    pub fn set_platform_wallet(env: Env, new_wallet: Address) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
+        
+        env.storage().instance().set(&DataKey::PlatformWallet, &new_wallet);
+        Ok(())
+    }
+    
+    /// Update platform fee (admin only)
+    /// 
+    /// Only streams created or renewed afterwards pay the new fee; open
+    /// streams keep the fee they started with.
+    /// 
+    /// # Arguments
+    /// * `fee_bps` - New fee in basis points (0-1000, i.e., 0-10%)
+    pub fn set_platform_fee(env: Env, fee_bps: i128) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
+        
+        if !(0..=1000).contains(&fee_bps) {
+            return Err(StreamError::InvalidFee);
+        }
+        
+        env.storage().instance().set(&DataKey::PlatformFeeBps, &fee_bps);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "fee_updated"),),
+            fee_bps,
+        );
         
-        // Recalculate rate (total remaining / remaining time)
-        let now = env.ledger().timestamp();
-        let remaining_time = stream.end_time.saturating_sub(now);
-        let remaining_amount = stream.total_amount.saturating_sub(stream.withdrawn);
-        stream.rate_per_second = remaining_amount
-            .checked_div(remaining_time as i128)
-            .ok_or(StreamError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Update grace period (admin only)
+    /// 
+    /// # Arguments
+    /// * `grace_seconds` - New grace period in seconds
+    pub fn set_grace_period(env: Env, grace_seconds: u64) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
         
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
+        env.storage().instance().set(&DataKey::GracePeriod, &grace_seconds);
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "stream_extended"), stream.subscriber.clone()),
-            (stream_id, additional_amount, additional_seconds),
+            (Symbol::new(&env, "grace_period_updated"),),
+            grace_seconds,
         );
         
         Ok(())
     }
     
-    /// Toggle auto-renewal for a stream
-    pub fn toggle_auto_renew(env: Env, stream_id: u64, enabled: bool) -> Result<(), StreamError> {
-        let mut stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)?;
-        
-        // Only subscriber can toggle
-        stream.subscriber.require_auth();
+    /// Get current platform fee in basis points
+    pub fn get_fee_bps(env: Env) -> i128 {
+        Self::bump_instance(&env);
+        Self::get_platform_fee_bps(&env)
+    }
+    
+    /// Get the platform fee a new stream to `creator` in `token` would lock in
+    /// 
+    /// Accounts for the creator's override and any volume tier they have reached.
+    pub fn get_creator_fee_bps(env: Env, creator: Address, token: Address) -> i128 {
+        Self::bump_instance(&env);
+        Self::resolve_fee_bps(&env, &creator, &token, None)
+    }
+    
+    /// Get the gross payouts a creator has received in a token
+    pub fn get_creator_volume(env: Env, creator: Address, token: Address) -> i128 {
+        let key = DataKey::CreatorVolume(creator, token);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+    
+    /// Give a creator their own platform fee (admin only)
+    /// 
+    /// Replaces the global fee for the creator's new and renewed streams.
+    /// 
+    /// # Arguments
+    /// * `fee_bps` - Fee in basis points (0-1000, i.e., 0-10%)
+    pub fn set_creator_fee_override(env: Env, creator: Address, fee_bps: i128) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
         
-        if stream.status != StreamStatus::Active {
-            return Err(StreamError::StreamNotActive);
+        if !(0..=1000).contains(&fee_bps) {
+            return Err(StreamError::InvalidFee);
         }
         
-        stream.auto_renew = enabled;
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
+        let key = DataKey::CreatorFeeOverride(creator.clone());
+        env.storage().persistent().set(&key, &fee_bps);
+        Self::bump_persistent(&env, &key, STREAM_TTL_BUFFER);
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "auto_renew_toggled"), stream.subscriber.clone()),
-            (stream_id, enabled),
+            (Symbol::new(&env, "creator_fee_set"), creator),
+            fee_bps,
         );
         
         Ok(())
     }
     
-    /// Renew an expired or expiring stream (for auto-renewal)
-    /// Can be called by subscriber or any external keeper
-    pub fn renew_stream(env: Env, stream_id: u64) -> Result<u64, StreamError> {
-        let mut stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)?;
+    /// Put a creator back on the global platform fee (admin only)
+    pub fn clear_creator_fee_override(env: Env, creator: Address) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
         
-        // Check if stream is eligible for renewal
-        let now = env.ledger().timestamp();
-        let grace_period = Self::get_grace_period(&env);
+        env.storage().persistent().remove(&DataKey::CreatorFeeOverride(creator.clone()));
         
-        // Can only renew if stream has ended or is within grace period
-        if stream.status == StreamStatus::Active && now < stream.end_time {
-            return Err(StreamError::StreamNotActive);
-        }
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "creator_fee_cleared"), creator),
+            (),
+        );
         
-        // Must have auto-renew enabled
-        if !stream.auto_renew {
-            return Err(StreamError::StreamNotActive);
+        Ok(())
+    }
+    
+    /// Set the volume fee tiers for a token (admin only)
+    /// 
+    /// Once a creator's lifetime volume in the token reaches a tier's
+    /// threshold, their fee drops to the tier's fee, including on streams
+    /// already running. An empty list removes volume pricing.
+    /// 
+    /// # Arguments
+    /// * `tiers` - Tiers with strictly increasing thresholds and
+    ///   non-increasing fees (each 0-1000 basis points)
+    pub fn set_volume_fee_tiers(env: Env, token: Address, tiers: Vec<VolumeFeeTier>) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
+        
+        let mut previous: Option<VolumeFeeTier> = None;
+        for tier in tiers.iter() {
+            if tier.min_volume <= 0 {
+                return Err(StreamError::InvalidAmount);
+            }
+            if !(0..=1000).contains(&tier.fee_bps) {
+                return Err(StreamError::InvalidFee);
+            }
+            if let Some(previous) = &previous {
+                if tier.min_volume <= previous.min_volume {
+                    return Err(StreamError::InvalidAmount);
+                }
+                if tier.fee_bps > previous.fee_bps {
+                    return Err(StreamError::InvalidFee);
+                }
+            }
+            previous = Some(tier);
         }
         
-        // Check if within grace period (not too late)
-        if now > stream.end_time + grace_period {
-            // Grace period expired, mark as completed
-            stream.status = StreamStatus::Completed;
-            env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
-            return Err(StreamError::InGracePeriod);
+        let key = DataKey::VolumeFeeTiers(token.clone());
+        if tiers.is_empty() {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, &tiers);
         }
         
-        // Create new stream with same parameters
-        let new_stream_id = Self::create_stream(
-            env.clone(),
-            stream.subscriber.clone(),
-            stream.creator.clone(),
-            stream.token.clone(),
-            stream.total_amount, // Same amount as original
-            stream.duration_seconds,
-            stream.tier_id,
-            true, // Keep auto-renew on
-        )?;
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "volume_tiers_updated"), token),
+            tiers.len(),
+        );
         
-        // Mark old stream as completed
-        stream.status = StreamStatus::Completed;
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
+        Ok(())
+    }
+    
+    /// Get the volume fee tiers for a token
+    pub fn get_volume_fee_tiers(env: Env, token: Address) -> Vec<VolumeFeeTier> {
+        Self::bump_instance(&env);
+        env.storage().instance().get(&DataKey::VolumeFeeTiers(token))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// Get current grace period in seconds
+    pub fn get_grace_period_seconds(env: Env) -> u64 {
+        Self::bump_instance(&env);
+        Self::get_grace_period(&env)
+    }
+    
+    /// Propose a new admin (admin only)
+    /// 
+    /// Rights only move once the proposed address calls `accept_admin`, so a
+    /// mistyped address can never lock the admin functions. A new proposal
+    /// replaces any previous one.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), StreamError> {
+        let admin = Self::require_admin(&env)?;
         
-        // Remove old active stream mapping (create_stream will add new one)
-        let key = DataKey::ActiveStream(stream.subscriber.clone(), stream.creator.clone());
-        env.storage().persistent().remove(&key);
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "stream_renewed"), stream.subscriber.clone()),
-            (stream_id, new_stream_id),
+            (Symbol::new(&env, "admin_proposed"), admin),
+            new_admin,
         );
         
-        Ok(new_stream_id)
+        Ok(())
     }
     
-    // === Creator Functions ===
-    
-    /// Creator terminates a stream (refunds subscriber for remaining time)
-    /// Use this to remove problematic subscribers
-    pub fn terminate_stream(env: Env, stream_id: u64) -> Result<i128, StreamError> {
-        let mut stream: Stream = env.storage().persistent().get(&DataKey::Stream(stream_id))
-            .ok_or(StreamError::StreamNotFound)?;
-        
-        // Only creator can terminate
-        stream.creator.require_auth();
+    /// Accept a pending admin proposal (proposed admin only)
+    pub fn accept_admin(env: Env) -> Result<(), StreamError> {
+        let pending: Address = env.storage().instance().get(&DataKey::PendingAdmin)
+            .ok_or(StreamError::NoPendingAdmin)?;
+        pending.require_auth();
         
-        if stream.status != StreamStatus::Active {
-            return Err(StreamError::AlreadyTerminated);
-        }
+        let previous: Address = env.storage().instance().get(&DataKey::Admin)
+            .ok_or(StreamError::NotInitialized)?;
         
-        let now = env.ledger().timestamp();
-        let elapsed = now.saturating_sub(stream.start_time);
+        env.storage().instance().set(&DataKey::Admin, &pending);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        Self::bump_instance(&env);
         
-        // Calculate what creator has earned
-        let earned = stream.rate_per_second
-            .checked_mul(elapsed as i128)
-            .ok_or(StreamError::Overflow)?;
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "admin_accepted"), previous),
+            pending,
+        );
         
-        // Creator forfeits pending earnings (no fee taken since creator initiated)
-        // Full remaining balance goes back to subscriber
-        let subscriber_refund = stream.total_amount.saturating_sub(stream.withdrawn);
+        Ok(())
+    }
+    
+    /// Withdraw a pending admin proposal (admin only)
+    pub fn cancel_admin_proposal(env: Env) -> Result<(), StreamError> {
+        let admin = Self::require_admin(&env)?;
         
-        let token_client = TokenClient::new(&env, &stream.token);
+        let pending: Address = env.storage().instance().get(&DataKey::PendingAdmin)
+            .ok_or(StreamError::NoPendingAdmin)?;
+        env.storage().instance().remove(&DataKey::PendingAdmin);
         
-        // Refund entire remaining balance to subscriber
-        if subscriber_refund > 0 {
-            token_client.transfer(&env.current_contract_address(), &stream.subscriber, &subscriber_refund);
-        }
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "admin_proposal_cancelled"), admin),
+            pending,
+        );
         
-        // Update stream status
-        stream.status = StreamStatus::Terminated;
-        env.storage().persistent().set(&DataKey::Stream(stream_id), &stream);
+        Ok(())
+    }
+    
+    /// Get the current admin
+    pub fn get_admin(env: Env) -> Result<Address, StreamError> {
+        Self::bump_instance(&env);
+        env.storage().instance().get(&DataKey::Admin)
+            .ok_or(StreamError::NotInitialized)
+    }
+    
+    /// Get the proposed admin awaiting acceptance, if any
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        Self::bump_instance(&env);
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+    
+    /// Approve a token for streaming, or update its limits (admin only)
+    /// 
+    /// # Arguments
+    /// * `token` - Token contract address
+    /// * `config` - Minimum deposit, minimum duration and optional maximum deposit
+    pub fn set_token_config(env: Env, token: Address, config: TokenConfig) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
         
-        // Remove from active streams
-        let key = DataKey::ActiveStream(stream.subscriber.clone(), stream.creator.clone());
-        env.storage().persistent().remove(&key);
+        if config.min_deposit < 0 {
+            return Err(StreamError::InvalidAmount);
+        }
+        if let Some(max) = config.max_deposit {
+            if max <= 0 || max < config.min_deposit {
+                return Err(StreamError::InvalidAmount);
+            }
+        }
         
-        // Decrement subscriber count
-        Self::increment_subscriber_count(&env, &stream.creator, false);
+        let key = DataKey::TokenConfig(token.clone());
+        if !env.storage().instance().has(&key) {
+            let mut tokens: Vec<Address> = env.storage().instance().get(&DataKey::AllowedTokens)
+                .unwrap_or(Vec::new(&env));
+            tokens.push_back(token.clone());
+            env.storage().instance().set(&DataKey::AllowedTokens, &tokens);
+        }
+        env.storage().instance().set(&key, &config);
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "stream_terminated"), stream.creator.clone()),
-            (stream_id, subscriber_refund),
+            (Symbol::new(&env, "token_allowed"), token),
+            (config.min_deposit, config.min_duration, config.max_deposit),
         );
         
-        Ok(subscriber_refund)
+        Ok(())
     }
     
-    /// Creator withdraws from all their active streams at once
+    /// Remove a token from the allowlist (admin only)
     /// 
-    /// # Returns
-    /// Total amount withdrawn (after fees)
-    pub fn withdraw_all(env: Env, creator: Address) -> Result<i128, StreamError> {
-        creator.require_auth();
-        
-        let stream_ids = Self::get_creator_streams(env.clone(), creator.clone());
-        let mut total_withdrawn: i128 = 0;
+    /// Existing streams in the token can still be withdrawn from, cancelled
+    /// and terminated, but no new funds can be streamed in it.
+    pub fn remove_token(env: Env, token: Address) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
+        
+        let key = DataKey::TokenConfig(token.clone());
+        if !env.storage().instance().has(&key) {
+            return Err(StreamError::TokenNotAllowed);
+        }
+        env.storage().instance().remove(&key);
         
-        for i in 0..stream_ids.len() {
-            if let Some(id) = stream_ids.get(i) {
-                // Try to withdraw from each stream, ignore errors (stream might be inactive)
-                if let Ok(amount) = Self::withdraw(env.clone(), id) {
-                    total_withdrawn = total_withdrawn.saturating_add(amount);
-                }
-            }
+        let mut tokens: Vec<Address> = env.storage().instance().get(&DataKey::AllowedTokens)
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = tokens.first_index_of(&token) {
+            tokens.remove(index);
         }
+        env.storage().instance().set(&DataKey::AllowedTokens, &tokens);
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "batch_withdrawal"), creator.clone()),
-            total_withdrawn,
+            (Symbol::new(&env, "token_removed"), token),
+            env.ledger().timestamp(),
         );
         
-        Ok(total_withdrawn)
+        Ok(())
     }
     
-    /// Get active subscriber count for a creator
-    pub fn get_active_subscriber_count(env: Env, creator: Address) -> u64 {
-        let key = DataKey::ActiveSubscriberCount(creator);
-        env.storage().persistent().get(&key).unwrap_or(0)
+    /// Get the limits for a token, if it is allowlisted
+    pub fn get_token_config(env: Env, token: Address) -> Option<TokenConfig> {
+        Self::bump_instance(&env);
+        env.storage().instance().get(&DataKey::TokenConfig(token))
     }
     
-    // === Admin Functions ===
+    /// Get all allowlisted tokens
+    pub fn get_allowed_tokens(env: Env) -> Vec<Address> {
+        Self::bump_instance(&env);
+        env.storage().instance().get(&DataKey::AllowedTokens)
+            .unwrap_or(Vec::new(&env))
+    }
     
-    /// Update platform wallet (admin only)
-    pub fn set_platform_wallet(env: Env, new_wallet: Address) -> Result<(), StreamError> {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin)
-            .ok_or(StreamError::NotInitialized)?;
-        admin.require_auth();
+    /// Set referral terms (admin only)
+    /// 
+    /// Streams keep the terms they were opened or renewed with. Lowering the
+    /// revenue cap does not change what creators already offer, but new
+    /// streams never get more than the cap.
+    pub fn set_referral_config(env: Env, config: ReferralConfig) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
+        
+        if config.fee_share_bps as i128 > BPS_DENOMINATOR || config.max_revenue_share_bps as i128 > BPS_DENOMINATOR {
+            return Err(StreamError::InvalidReferral);
+        }
+        
+        env.storage().instance().set(&DataKey::ReferralConfig, &config);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "referral_config_updated"),),
+            (config.fee_share_bps, config.max_revenue_share_bps),
+        );
         
-        env.storage().instance().set(&DataKey::PlatformWallet, &new_wallet);
         Ok(())
     }
     
-    /// Update platform fee (admin only)
+    /// Get the referral terms (no referral shares if never set)
+    pub fn get_referral_config(env: Env) -> ReferralConfig {
+        env.storage().instance().get(&DataKey::ReferralConfig).unwrap_or_default()
+    }
+    
+    /// Halt fund flows in an emergency (admin only)
     /// 
-    /// # Arguments
-    /// * `fee_bps` - New fee in basis points (0-1000, i.e., 0-10%)
-    pub fn set_platform_fee(env: Env, fee_bps: i128) -> Result<(), StreamError> {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin)
-            .ok_or(StreamError::NotInitialized)?;
-        admin.require_auth();
+    /// Blocks creating, extending, renewing, expiring and withdrawing from streams.
+    /// `cancel` stays available so subscribers can always recover unstreamed funds.
+    pub fn pause(env: Env) -> Result<(), StreamError> {
+        let admin = Self::require_admin(&env)?;
         
-        if fee_bps < 0 || fee_bps > 1000 {
-            return Err(StreamError::InvalidFee);
-        }
+        env.storage().instance().set(&DataKey::Paused, &true);
         
-        env.storage().instance().set(&DataKey::PlatformFeeBps, &fee_bps);
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "contract_paused"), admin),
+            env.ledger().timestamp(),
+        );
+        
+        Ok(())
+    }
+    
+    /// Resume normal operation after a pause (admin only)
+    pub fn unpause(env: Env) -> Result<(), StreamError> {
+        let admin = Self::require_admin(&env)?;
+        
+        env.storage().instance().remove(&DataKey::Paused);
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "fee_updated"),),
-            fee_bps,
+            (Symbol::new(&env, "contract_unpaused"), admin),
+            env.ledger().timestamp(),
         );
         
         Ok(())
     }
     
-    /// Update grace period (admin only)
+    /// Check whether the contract is paused
+    pub fn is_paused(env: Env) -> bool {
+        Self::bump_instance(&env);
+        env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
+    }
+    
+    // === Upgrades ===
+    
+    /// Replace the contract code with an uploaded WASM (admin only)
     /// 
-    /// # Arguments
-    /// * `grace_seconds` - New grace period in seconds
-    pub fn set_grace_period(env: Env, grace_seconds: u64) -> Result<(), StreamError> {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin)
-            .ok_or(StreamError::NotInitialized)?;
-        admin.require_auth();
+    /// Escrowed streams stay in place. If the new code bumps the storage
+    /// layout, call `migrate` until it reports nothing left to do.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), StreamError> {
+        Self::require_admin(&env)?;
         
-        env.storage().instance().set(&DataKey::GracePeriod, &grace_seconds);
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
         
         // Emit event
         env.events().publish(
-            (Symbol::new(&env, "grace_period_updated"),),
-            grace_seconds,
+            (Symbol::new(&env, "contract_upgraded"),),
+            new_wasm_hash,
         );
         
         Ok(())
     }
     
-    /// Get current platform fee in basis points
-    pub fn get_fee_bps(env: Env) -> i128 {
-        Self::get_platform_fee_bps(&env)
+    /// Bring persisted streams up to the current storage layout (admin only)
+    /// 
+    /// Processes at most `limit` stream IDs per call so large deployments can
+    /// migrate across several transactions. Until a stream has been reached,
+    /// every call on it (including `cancel` and `withdraw`) fails with
+    /// `MigrationPending`, so run the batches back to back after an upgrade.
+    /// 
+    /// # Returns
+    /// Number of stream IDs still waiting to be migrated (0 when done)
+    pub fn migrate(env: Env, limit: u32) -> Result<u64, StreamError> {
+        Self::require_admin(&env)?;
+        
+        let from_version = Self::get_schema_version(env.clone());
+        if from_version >= SCHEMA_VERSION {
+            return Ok(0);
+        }
+        
+        let next_id: u64 = env.storage().instance().get(&DataKey::NextStreamId)
+            .ok_or(StreamError::NotInitialized)?;
+        let mut cursor: u64 = env.storage().instance().get(&DataKey::MigrationCursor)
+            .unwrap_or(1);
+        let stop = cursor.saturating_add(limit as u64).min(next_id);
+        
+        while cursor < stop {
+            Self::migrate_stream_entry(&env, cursor, from_version);
+            cursor += 1;
+        }
+        
+        if cursor < next_id {
+            env.storage().instance().set(&DataKey::MigrationCursor, &cursor);
+            return Ok(next_id - cursor);
+        }
+        
+        env.storage().instance().remove(&DataKey::MigrationCursor);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        
+        // Emit event
+        env.events().publish(
+            (Symbol::new(&env, "migrated"),),
+            (from_version, SCHEMA_VERSION),
+        );
+        
+        Ok(0)
     }
     
-    /// Get current grace period in seconds
-    pub fn get_grace_period_seconds(env: Env) -> u64 {
-        Self::get_grace_period(&env)
+    /// Get the storage layout version persisted data conforms to
+    pub fn get_schema_version(env: Env) -> u32 {
+        // Deployments predating versioning never stored one
+        env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(1)
     }
     
-    /// Transfer admin rights (admin only)
-    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), StreamError> {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin)
-            .ok_or(StreamError::NotInitialized)?;
-        admin.require_auth();
+    /// Helper: Rewrite one stored stream in the current layout
+    /// 
+    /// Streams are read as raw field maps so entries written by any older
+    /// layout can be decoded; fields they lack get the value that reproduces
+    /// their old behaviour. Entries already in the current layout are
+    /// left as they are.
+    fn migrate_stream_entry(env: &Env, stream_id: u64, from_version: u32) {
+        let key = DataKey::Stream(stream_id);
+        let mut fields: Map<Symbol, Val> = match env.storage().persistent().get(&key) {
+            Some(fields) => fields,
+            None => return,
+        };
         
-        env.storage().instance().set(&DataKey::Admin, &new_admin);
-        Ok(())
+        // v2: keeper renewals were not possible before
+        Self::default_field(
+            env,
+            &mut fields,
+            "renewal_allowance",
+            RenewalAllowance { remaining_cycles: 0, max_amount_per_cycle: 0 }.into_val(env),
+        );
+        
+        // v3: payouts used to read the global fee, which is what they keep paying
+        Self::default_field(
+            env,
+            &mut fields,
+            "fee_bps",
+            Self::get_platform_fee_bps(env).into_val(env),
+        );
+        
+        // v4: accrual ran linearly from the start with nothing settled
+        Self::default_field(env, &mut fields, "accrued", 0i128.into_val(env));
+        let start_time = fields.get(Symbol::new(env, "start_time"))
+            .unwrap_or_else(|| panic!("Stream entry could not be migrated"));
+        Self::default_field(env, &mut fields, "checkpoint_time", start_time);
+        
+        // v5: terminations carried no reason
+        Self::default_field(env, &mut fields, "termination_reason", 0u32.into_val(env));
+        
+        // v8: streams could not be paused
+        Self::default_field(env, &mut fields, "paused_at", 0u64.into_val(env));
+        Self::default_field(env, &mut fields, "pause_limit", 0u64.into_val(env));
+        Self::default_field(env, &mut fields, "pause_count", 0u32.into_val(env));
+        Self::default_field(env, &mut fields, "pause_window_start", 0u64.into_val(env));
+        
+        // v9: streams had no trial or cliff
+        Self::default_field(env, &mut fields, "trial_end", 0u64.into_val(env));
+        Self::default_field(env, &mut fields, "cliff_end", 0u64.into_val(env));
+        
+        // v10: everything streamed linearly from the start
+        Self::default_field(env, &mut fields, "schedule", ReleaseSchedule::Linear.into_val(env));
+        Self::default_field(env, &mut fields, "accrual_start", start_time);
+        
+        // v11: no stream was referred
+        Self::default_field(env, &mut fields, "referrer", Option::<Address>::None.into_val(env));
+        Self::default_field(env, &mut fields, "referral_fee_bps", 0u32.into_val(env));
+        Self::default_field(env, &mut fields, "referral_revenue_bps", 0u32.into_val(env));
+        
+        // v14: custom renewals charged the whole deposit
+        let total_amount = fields.get(Symbol::new(env, "total_amount"))
+            .unwrap_or_else(|| panic!("Stream entry could not be migrated"));
+        Self::default_field(env, &mut fields, "cycle_amount", total_amount);
+        
+        // Decoding proves the entry now matches the current layout
+        let stream = Stream::try_from_val(env, fields.as_val())
+            .unwrap_or_else(|_| panic!("Stream entry could not be migrated"));
+        
+        // v6: rebuild the indexes from the streams themselves (visited in ID
+        // order, so histories keep their order) and drop the old lists
+        let mut history_slots = None;
+        if from_version < 6 {
+            history_slots = Some(HistorySlots {
+                subscriber_index: Self::add_to_address_streams(env, &stream.subscriber, stream.id, true),
+                creator_index: Self::add_to_address_streams(env, &stream.creator, stream.id, false),
+            });
+            if stream.status == StreamStatus::Active {
+                Self::add_to_active_sets(env, stream.id, &stream.subscriber, &stream.creator);
+            }
+            env.storage().persistent().remove(&LegacyDataKey::SubscriberStreams(stream.subscriber.clone()));
+            env.storage().persistent().remove(&LegacyDataKey::CreatorStreams(stream.creator.clone()));
+        }
+        
+        // v7: the one active stream per pair moves to its tier and token key
+        if from_version < 7 && stream.status == StreamStatus::Active {
+            env.storage().persistent().remove(&LegacyDataKey::ActiveStream(stream.subscriber.clone(), stream.creator.clone()));
+            env.storage().persistent().set(&Self::active_stream_key(&stream), &stream.id);
+            let pair_key = DataKey::PairActiveCount(stream.subscriber.clone(), stream.creator.clone());
+            env.storage().persistent().set(&pair_key, &1u32);
+        }
+        
+        // v12: record where the stream sits in each history
+        if from_version < 12 {
+            let slots = history_slots.or_else(|| {
+                Some(HistorySlots {
+                    subscriber_index: Self::find_history_index(env, &stream.subscriber, true, stream.id)?,
+                    creator_index: Self::find_history_index(env, &stream.creator, false, stream.id)?,
+                })
+            });
+            if let Some(slots) = slots {
+                env.storage().persistent().set(&DataKey::HistorySlots(stream.id), &slots);
+            }
+        }
+        
+        // v13: count active tier streams per subscriber, creator and tier
+        if from_version < 13 && (stream.status == StreamStatus::Active || stream.status == StreamStatus::Paused) {
+            Self::adjust_tier_count(env, &stream, stream.tier_id, true);
+        }
+        
+        Self::save_stream(env, &stream);
+    }
+    
+    /// Helper: Insert a field into a raw stream entry if it is missing
+    fn default_field(env: &Env, fields: &mut Map<Symbol, Val>, name: &str, value: Val) {
+        let name = Symbol::new(env, name);
+        if !fields.contains_key(name.clone()) {
+            fields.set(name, value);
+        }
     }
 }
+
+mod test;
+mod test_invariants;
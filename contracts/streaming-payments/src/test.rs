@@ -0,0 +1,2719 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::{
+    testutils::{
+        storage::{Instance as _, Persistent as _},
+        Address as _, Events, Ledger,
+    },
+    token::{Client as TokenClient, StellarAssetClient},
+    vec, Address, BytesN, Env, Map, Symbol, Val,
+};
+
+/// Ledger time the tests start at
+const START: u64 = 1_000;
+/// Balance minted to each subscriber
+const MINTED: i128 = 10_000_000;
+/// Default stream deposit (1000 per second over `DURATION`)
+const AMOUNT: i128 = 1_000_000;
+/// Default stream duration
+const DURATION: u64 = 1_000;
+
+struct Setup<'a> {
+    env: Env,
+    client: StreamingPaymentsContractClient<'a>,
+    token: TokenClient<'a>,
+    admin: Address,
+    platform: Address,
+    subscriber: Address,
+    creator: Address,
+}
+
+impl Setup<'_> {
+    fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|li| li.timestamp = START);
+
+        let admin = Address::generate(&env);
+        let platform = Address::generate(&env);
+        let subscriber = Address::generate(&env);
+        let creator = Address::generate(&env);
+
+        let token_admin = Address::generate(&env);
+        let sac = env.register_stellar_asset_contract_v2(token_admin);
+        let token = TokenClient::new(&env, &sac.address());
+        StellarAssetClient::new(&env, &sac.address()).mint(&subscriber, &MINTED);
+
+        let contract_id = env.register_contract(None, StreamingPaymentsContract);
+        let client = StreamingPaymentsContractClient::new(&env, &contract_id);
+        client.initialize(&admin, &platform, &None, &None);
+        client.set_token_config(&token.address, &open_limits());
+
+        Setup { env, client, token, admin, platform, subscriber, creator }
+    }
+
+    fn advance(&self, seconds: u64) {
+        self.env.ledger().with_mut(|li| li.timestamp += seconds);
+    }
+
+    fn fund(&self, who: &Address, amount: i128) {
+        StellarAssetClient::new(&self.env, &self.token.address).mint(who, &amount);
+    }
+
+    fn create_default(&self) -> u64 {
+        self.client.create_stream(
+            &self.subscriber,
+            &self.creator,
+            &self.token.address,
+            &AMOUNT,
+            &DURATION,
+            &0,
+            &false,
+            &None,
+            &None,
+        )
+    }
+
+    fn create_renewable(&self, allowance: Option<RenewalAllowance>) -> u64 {
+        self.client.create_stream(
+            &self.subscriber,
+            &self.creator,
+            &self.token.address,
+            &AMOUNT,
+            &DURATION,
+            &0,
+            &true,
+            &allowance,
+            &None,
+        )
+    }
+
+    /// Adds a tier priced like the default stream to the creator's catalogue
+    fn create_tier(&self) -> u32 {
+        self.client.create_tier(&self.creator, &self.token.address, &AMOUNT, &DURATION)
+    }
+
+    fn balance(&self, who: &Address) -> i128 {
+        self.token.balance(who)
+    }
+
+    fn contract_balance(&self) -> i128 {
+        self.token.balance(&self.client.address)
+    }
+
+    /// Asserts (subscriber, creator, platform, contract) balances
+    fn assert_balances(&self, subscriber: i128, creator: i128, platform: i128, contract: i128) {
+        assert_eq!(self.balance(&self.subscriber), subscriber, "subscriber balance");
+        assert_eq!(self.balance(&self.creator), creator, "creator balance");
+        assert_eq!(self.balance(&self.platform), platform, "platform balance");
+        assert_eq!(self.contract_balance(), contract, "contract balance");
+    }
+}
+
+/// Token limits that accept any positive stream
+fn open_limits() -> TokenConfig {
+    TokenConfig { min_deposit: 0, min_duration: 0, max_deposit: None }
+}
+
+/// Platform fee at the default 2%
+fn fee_of(amount: i128) -> i128 {
+    amount * DEFAULT_PLATFORM_FEE_BPS / BPS_DENOMINATOR
+}
+
+// === Initialization ===
+
+#[test]
+fn test_initialize_sets_defaults() {
+    let s = Setup::new();
+    assert_eq!(s.client.get_fee_bps(), DEFAULT_PLATFORM_FEE_BPS);
+    assert_eq!(s.client.get_grace_period_seconds(), DEFAULT_GRACE_PERIOD);
+}
+
+#[test]
+fn test_initialize_with_custom_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, StreamingPaymentsContract);
+    let client = StreamingPaymentsContractClient::new(&env, &contract_id);
+
+    client.initialize(&Address::generate(&env), &Address::generate(&env), &Some(500), &Some(3_600));
+
+    assert_eq!(client.get_fee_bps(), 500);
+    assert_eq!(client.get_grace_period_seconds(), 3_600);
+}
+
+#[test]
+fn test_initialize_twice_fails() {
+    let s = Setup::new();
+    let result = s.client.try_initialize(&s.admin, &s.platform, &None, &None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_initialize_rejects_excessive_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, StreamingPaymentsContract);
+    let client = StreamingPaymentsContractClient::new(&env, &contract_id);
+
+    let result = client.try_initialize(&Address::generate(&env), &Address::generate(&env), &Some(1_001), &None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_create_stream_before_initialize_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, StreamingPaymentsContract);
+    let client = StreamingPaymentsContractClient::new(&env, &contract_id);
+    let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+
+    let result = client.try_create_stream(
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &sac.address(),
+        &AMOUNT,
+        &DURATION,
+        &0,
+        &false,
+        &None,
+        &None,
+    );
+    assert_eq!(result, Err(Ok(StreamError::NotInitialized)));
+}
+
+// === Stream creation ===
+
+#[test]
+fn test_create_stream_escrows_deposit() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    assert_eq!(id, 1);
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.subscriber, s.subscriber);
+    assert_eq!(stream.creator, s.creator);
+    assert_eq!(stream.total_amount, AMOUNT);
+    assert_eq!(stream.rate_per_second, 1_000);
+    assert_eq!(stream.start_time, START);
+    assert_eq!(stream.end_time, START + DURATION);
+    assert_eq!(stream.withdrawn, 0);
+    assert_eq!(stream.status, StreamStatus::Active);
+    assert_eq!(stream.platform_wallet, s.platform);
+
+    assert!(s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 1);
+    assert_eq!(s.client.get_subscriber_streams(&s.subscriber, &0, &10), soroban_sdk::vec![&s.env, id]);
+    assert_eq!(s.client.get_creator_streams(&s.creator, &0, &10), soroban_sdk::vec![&s.env, id]);
+}
+
+#[test]
+fn test_create_stream_requires_subscriber_auth() {
+    let s = Setup::new();
+    s.create_default();
+
+    let auths = s.env.auths();
+    assert_eq!(auths[0].0, s.subscriber);
+}
+
+#[test]
+fn test_create_stream_rejects_invalid_input() {
+    let s = Setup::new();
+
+    let zero_amount = s.client.try_create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &0, &DURATION, &0, &false, &None, &None,
+    );
+    assert_eq!(zero_amount, Err(Ok(StreamError::InvalidAmount)));
+
+    let zero_duration = s.client.try_create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &0, &0, &false, &None, &None,
+    );
+    assert_eq!(zero_duration, Err(Ok(StreamError::InvalidDuration)));
+
+    s.assert_balances(MINTED, 0, 0, 0);
+}
+
+#[test]
+fn test_create_stream_rejects_duplicate_active_stream() {
+    let s = Setup::new();
+    s.create_default();
+
+    let result = s.client.try_create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+    assert_eq!(result, Err(Ok(StreamError::StreamAlreadyExists)));
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+}
+
+#[test]
+fn test_concurrent_streams_per_tier_and_token() {
+    let s = Setup::new();
+    let basic = s.create_tier();
+    let addon = s.client.create_tier(&s.creator, &s.token.address, &(AMOUNT / 2), &DURATION);
+
+    let other_token = s.env.register_stellar_asset_contract_v2(Address::generate(&s.env)).address();
+    s.client.set_token_config(&other_token, &open_limits());
+    StellarAssetClient::new(&s.env, &other_token).mint(&s.subscriber, &AMOUNT);
+
+    let first = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &basic, &None);
+    let second = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &addon, &None);
+    let custom = s.client.create_stream(
+        &s.subscriber, &s.creator, &other_token, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+
+    assert_eq!(s.client.get_active_stream(&s.subscriber, &s.creator, &basic, &s.token.address), Some(first));
+    assert_eq!(s.client.get_active_stream(&s.subscriber, &s.creator, &addon, &s.token.address), Some(second));
+    assert_eq!(s.client.get_active_stream(&s.subscriber, &s.creator, &0, &other_token), Some(custom));
+    assert_eq!(s.client.get_active_stream(&s.subscriber, &s.creator, &0, &s.token.address), None);
+    assert!(s.client.has_active_tier(&s.subscriber, &s.creator, &addon));
+
+    // Still one subscriber as far as the creator is concerned
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 1);
+    assert_eq!(s.client.get_subscriber_active_count(&s.subscriber), 3);
+
+    // The same tier and token can't be held twice
+    assert_eq!(
+        s.client.try_subscribe_to_tier(&s.subscriber, &s.creator, &basic, &None),
+        Err(Ok(StreamError::StreamAlreadyExists)),
+    );
+
+    s.client.cancel(&second);
+    assert!(!s.client.has_active_tier(&s.subscriber, &s.creator, &addon));
+    assert!(s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 1);
+
+    s.client.cancel(&first);
+    s.client.cancel(&custom);
+    assert!(!s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 0);
+}
+
+#[test]
+fn test_create_stream_rejects_allowance_without_auto_renew() {
+    let s = Setup::new();
+    let allowance = RenewalAllowance { remaining_cycles: 2, max_amount_per_cycle: AMOUNT };
+
+    let result = s.client.try_create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &Some(allowance), &None,
+    );
+    assert_eq!(result, Err(Ok(StreamError::InvalidAllowance)));
+
+    let empty = RenewalAllowance { remaining_cycles: 0, max_amount_per_cycle: AMOUNT };
+    let result = s.client.try_create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &true, &Some(empty), &None,
+    );
+    assert_eq!(result, Err(Ok(StreamError::InvalidAllowance)));
+}
+
+// === Withdrawals ===
+
+#[test]
+fn test_get_withdrawable_accrues_net_of_fee() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    assert_eq!(s.client.get_withdrawable(&id), 0);
+
+    s.advance(250);
+    assert_eq!(s.client.get_withdrawable(&id), 250_000 - fee_of(250_000));
+
+    // Accrual stops at end_time
+    s.advance(5_000);
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT - fee_of(AMOUNT));
+}
+
+#[test]
+fn test_withdraw_mid_stream() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(400);
+    let paid = s.client.withdraw(&id);
+
+    assert_eq!(paid, 400_000 - fee_of(400_000));
+    s.assert_balances(MINTED - AMOUNT, paid, fee_of(400_000), AMOUNT - 400_000);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.withdrawn, 400_000);
+    assert_eq!(stream.status, StreamStatus::Active);
+    assert_eq!(s.client.get_withdrawable(&id), 0);
+}
+
+#[test]
+fn test_withdraw_requires_creator_auth() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(10);
+    s.client.withdraw(&id);
+
+    let auths = s.env.auths();
+    assert_eq!(auths[0].0, s.creator);
+}
+
+#[test]
+fn test_withdraw_with_nothing_accrued_fails() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    assert_eq!(s.client.try_withdraw(&id), Err(Ok(StreamError::InsufficientBalance)));
+
+    s.advance(100);
+    s.client.withdraw(&id);
+    assert_eq!(s.client.try_withdraw(&id), Err(Ok(StreamError::InsufficientBalance)));
+}
+
+#[test]
+fn test_withdraw_after_end_completes_stream() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(300);
+    let first = s.client.withdraw(&id);
+    s.advance(DURATION);
+    let second = s.client.withdraw(&id);
+
+    assert_eq!(first + second, AMOUNT - fee_of(300_000) - fee_of(700_000));
+    s.assert_balances(MINTED - AMOUNT, first + second, fee_of(300_000) + fee_of(700_000), 0);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.status, StreamStatus::Completed);
+    assert_eq!(stream.withdrawn, AMOUNT);
+    assert!(!s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 0);
+}
+
+#[test]
+fn test_withdraw_unknown_stream_fails() {
+    let s = Setup::new();
+    assert_eq!(s.client.try_withdraw(&42), Err(Ok(StreamError::StreamNotFound)));
+    assert_eq!(s.client.try_get_stream(&42).err(), Some(Ok(StreamError::StreamNotFound)));
+}
+
+#[test]
+fn test_withdraw_all_across_streams() {
+    let s = Setup::new();
+    let first = s.create_default();
+
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    let second = s.client.create_stream(
+        &other, &s.creator, &s.token.address, &(AMOUNT * 2), &DURATION, &0, &false, &None, &None,
+    );
+
+    s.advance(500);
+    assert_eq!(
+        s.client.get_total_accrued(&s.creator, &0, &10),
+        500_000 - fee_of(500_000) + 1_000_000 - fee_of(1_000_000),
+    );
+
+    let total = s.client.withdraw_all(&s.creator, &0, &MAX_PAGE_SIZE);
+
+    assert_eq!(total, 1_500_000 - fee_of(500_000) - fee_of(1_000_000));
+    s.assert_balances(MINTED - AMOUNT, total, fee_of(500_000) + fee_of(1_000_000), 1_500_000);
+    assert_eq!(s.client.get_stream(&first).withdrawn, 500_000);
+    assert_eq!(s.client.get_stream(&second).withdrawn, 1_000_000);
+    assert_eq!(s.client.get_total_accrued(&s.creator, &0, &10), 0);
+}
+
+#[test]
+fn test_total_accrued_only_counts_active_streams() {
+    let s = Setup::new();
+    let cancelled = s.create_default();
+    s.advance(DURATION / 4);
+    s.client.cancel(&cancelled);
+
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    s.client.create_stream(&other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None);
+    s.advance(DURATION / 4);
+
+    // The cancelled stream no longer takes up the first page
+    assert_eq!(s.client.get_total_accrued(&s.creator, &0, &1), AMOUNT / 4 - fee_of(AMOUNT / 4));
+    assert_eq!(s.client.get_total_accrued(&s.creator, &1, &1), 0);
+}
+
+#[test]
+fn test_full_page_fits_default_budget() {
+    let s = Setup::new();
+    s.env.budget().reset_unlimited();
+
+    // Worst case: every payout fans out across a full revenue split
+    let mut shares = vec![&s.env];
+    for _ in 0..MAX_SPLIT_RECIPIENTS {
+        shares.push_back(share(&Address::generate(&s.env), 1_000));
+    }
+    s.client.set_revenue_split(&s.creator, &shares, &MAX_PAGE_SIZE);
+    for _ in 0..MAX_PAGE_SIZE {
+        let subscriber = Address::generate(&s.env);
+        s.fund(&subscriber, MINTED);
+        s.client.create_stream(
+            &subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+        );
+    }
+    s.advance(DURATION / 2);
+
+    s.env.budget().reset_default();
+    let paid = s.client.withdraw_all(&s.creator, &0, &u32::MAX);
+    let net = AMOUNT / 2 - fee_of(AMOUNT / 2);
+    assert_eq!(paid, net * MAX_PAGE_SIZE as i128);
+
+    s.advance(DURATION / 4);
+    s.env.budget().reset_default();
+    assert_eq!(s.client.clear_revenue_split(&s.creator, &u32::MAX), 0);
+}
+
+#[test]
+fn test_withdraw_all_skips_inactive_streams() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(100);
+    s.client.cancel(&id);
+
+    assert_eq!(s.client.withdraw_all(&s.creator, &0, &MAX_PAGE_SIZE), 0);
+}
+
+#[test]
+fn test_withdraw_all_reaches_streams_older_than_a_page() {
+    let s = Setup::new();
+    s.env.budget().reset_unlimited();
+    let old = s.client.create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &(DURATION * 10), &0, &false, &None, &None,
+    );
+
+    // Push the old stream more than a page back in the creator's history
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    for _ in 0..MAX_PAGE_SIZE {
+        let id = s.client.create_stream(
+            &other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+        );
+        s.client.cancel(&id);
+    }
+    assert_eq!(s.client.get_creator_stream_count(&s.creator), MAX_PAGE_SIZE + 1);
+
+    s.advance(DURATION);
+    let paid = s.client.withdraw_all(&s.creator, &0, &MAX_PAGE_SIZE);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    assert_eq!(paid, AMOUNT / 10 - fee_of(AMOUNT / 10));
+    assert_eq!(s.client.get_stream(&old).withdrawn, AMOUNT / 10);
+}
+
+#[test]
+fn test_withdraw_all_pages_from_the_end_reach_every_stream() {
+    let s = Setup::new();
+    let first = s.create_default();
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    let second = s.client.create_stream(
+        &other, &s.creator, &s.token.address, &AMOUNT, &(DURATION * 2), &0, &false, &None, &None,
+    );
+
+    // The first stream completes and the second moves into its slot
+    s.advance(DURATION);
+    assert_eq!(s.client.withdraw_all(&s.creator, &1, &1), AMOUNT / 2 - fee_of(AMOUNT / 2));
+    assert_eq!(s.client.withdraw_all(&s.creator, &0, &1), AMOUNT - fee_of(AMOUNT));
+    assert_eq!(s.client.get_stream(&first).status, StreamStatus::Completed);
+    assert_eq!(s.client.get_stream(&second).withdrawn, AMOUNT / 2);
+}
+
+#[test]
+fn test_withdraw_batch_only_visits_requested_page() {
+    let s = Setup::new();
+    let first = s.create_default();
+
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    let second = s.client.create_stream(
+        &other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+
+    s.advance(DURATION);
+    let paid = s.client.withdraw_batch(&s.creator, &1, &5);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    assert_eq!(paid, AMOUNT - fee_of(AMOUNT));
+    assert_eq!(s.client.get_stream(&first).withdrawn, 0);
+    assert_eq!(s.client.get_stream(&second).withdrawn, AMOUNT);
+
+    // Pages past the end are empty rather than errors
+    assert_eq!(s.client.withdraw_batch(&s.creator, &10, &5), 0);
+}
+
+#[test]
+fn test_withdraw_pays_exact_total_despite_uneven_rate() {
+    let s = Setup::new();
+    let amount = 1_000_999;
+    let id = s.client.create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &amount, &DURATION, &0, &false, &None, &None,
+    );
+
+    s.advance(333);
+    let first = s.client.withdraw(&id);
+    assert_eq!(s.client.get_stream(&id).withdrawn, amount * 333 / DURATION as i128);
+
+    s.advance(DURATION);
+    let second = s.client.withdraw(&id);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.withdrawn, amount);
+    assert_eq!(stream.status, StreamStatus::Completed);
+    assert_eq!(s.contract_balance(), 0);
+    assert_eq!(first + second + s.balance(&s.platform), amount);
+}
+
+#[test]
+fn test_cancel_after_end_pays_creator_exactly_once() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(DURATION * 3);
+    let (creator_side, refund) = s.client.cancel(&id);
+
+    assert_eq!(creator_side, AMOUNT);
+    assert_eq!(refund, 0);
+    s.assert_balances(MINTED - AMOUNT, AMOUNT - fee_of(AMOUNT), fee_of(AMOUNT), 0);
+}
+
+// === Cancellation ===
+
+#[test]
+fn test_cancel_refunds_unstreamed_balance() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(250);
+    let (creator_side, refund) = s.client.cancel(&id);
+
+    assert_eq!(creator_side, 250_000);
+    assert_eq!(refund, 750_000);
+    s.assert_balances(MINTED - 250_000, 250_000 - fee_of(250_000), fee_of(250_000), 0);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.status, StreamStatus::Cancelled);
+    assert!(!s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 0);
+}
+
+#[test]
+fn test_cancel_after_partial_withdraw() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(200);
+    let withdrawn = s.client.withdraw(&id);
+    s.advance(200);
+    let (creator_side, refund) = s.client.cancel(&id);
+
+    assert_eq!(creator_side, 200_000);
+    assert_eq!(refund, 600_000);
+    s.assert_balances(
+        MINTED - 400_000,
+        withdrawn + 200_000 - fee_of(200_000),
+        fee_of(200_000) * 2,
+        0,
+    );
+}
+
+#[test]
+fn test_cancel_requires_active_stream() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.cancel(&id);
+    assert_eq!(s.client.try_cancel(&id), Err(Ok(StreamError::StreamNotActive)));
+
+    // A new stream with the same creator is allowed once cancelled
+    let again = s.create_default();
+    assert_eq!(again, 2);
+}
+
+#[test]
+fn test_cancel_requires_subscriber_auth() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.cancel(&id);
+
+    let auths = s.env.auths();
+    assert_eq!(auths[0].0, s.subscriber);
+}
+
+// === Extension ===
+
+#[test]
+fn test_extend_stream_adds_funds_and_time() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.extend_stream(&id, &500_000, &500);
+    s.assert_balances(MINTED - 1_500_000, 0, 0, 1_500_000);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.total_amount, 1_500_000);
+    assert_eq!(stream.end_time, START + 1_500);
+    assert_eq!(stream.duration_seconds, 1_500);
+    assert_eq!(stream.rate_per_second, 1_000);
+
+    s.advance(1_500);
+    let paid = s.client.withdraw(&id);
+    assert_eq!(paid, 1_500_000 - fee_of(1_500_000));
+    s.assert_balances(MINTED - 1_500_000, paid, fee_of(1_500_000), 0);
+}
+
+#[test]
+fn test_extend_stream_keeps_past_accrual() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    // Half the deposit has streamed when a much larger extension comes in
+    s.advance(DURATION / 2);
+    s.client.extend_stream(&id, &(AMOUNT * 4), &(DURATION / 2));
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.accrued, AMOUNT / 2);
+    assert_eq!(stream.checkpoint_time, START + DURATION / 2);
+    assert_eq!(stream.rate_per_second, 4_500);
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT / 2 - fee_of(AMOUNT / 2));
+
+    // The new funds only stream from the extension onwards
+    s.advance(DURATION / 4);
+    let earned = AMOUNT / 2 + (AMOUNT / 2 + AMOUNT * 4) / 4;
+    let paid = s.client.withdraw(&id);
+    assert_eq!(paid, earned - fee_of(earned));
+
+    // Cancelling now splits by the checkpointed schedule
+    let (creator_total, refund) = s.client.cancel(&id);
+    assert_eq!(creator_total, 0);
+    assert_eq!(refund, AMOUNT * 5 - earned);
+}
+
+#[test]
+fn test_extend_stream_rejects_invalid_input() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    assert_eq!(s.client.try_extend_stream(&id, &0, &10), Err(Ok(StreamError::InvalidAmount)));
+    assert_eq!(s.client.try_extend_stream(&id, &10, &0), Err(Ok(StreamError::InvalidDuration)));
+
+    s.client.cancel(&id);
+    assert_eq!(s.client.try_extend_stream(&id, &10, &10), Err(Ok(StreamError::StreamNotActive)));
+}
+
+#[test]
+fn test_extend_stream_rejects_ended_stream() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(DURATION + 500);
+    assert_eq!(s.client.try_extend_stream(&id, &AMOUNT, &300), Err(Ok(StreamError::StreamNotActive)));
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT - fee_of(AMOUNT));
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+}
+
+#[test]
+fn test_extend_stream_with_uneven_amounts_streams_everything() {
+    let s = Setup::new();
+    let id = s.client.create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &999_999, &7, &0, &false, &None, &None,
+    );
+
+    s.advance(3);
+    s.client.withdraw(&id);
+    s.client.extend_stream(&id, &12_345, &11);
+
+    s.advance(100);
+    s.client.withdraw(&id);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.withdrawn, 999_999 + 12_345);
+    assert_eq!(stream.status, StreamStatus::Completed);
+    assert_eq!(s.contract_balance(), 0);
+}
+
+// === Renewal ===
+
+#[test]
+fn test_toggle_auto_renew() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.toggle_auto_renew(&id, &true, &None);
+    assert!(s.client.get_stream(&id).auto_renew);
+
+    s.client.toggle_auto_renew(&id, &false, &None);
+    assert!(!s.client.get_stream(&id).auto_renew);
+}
+
+#[test]
+fn test_toggle_auto_renew_manages_token_approval() {
+    let s = Setup::new();
+    let id = s.create_default();
+    let allowance = RenewalAllowance { remaining_cycles: 3, max_amount_per_cycle: AMOUNT };
+
+    s.client.toggle_auto_renew(&id, &true, &Some(allowance.clone()));
+    assert_eq!(s.client.get_stream(&id).renewal_allowance, allowance);
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), AMOUNT * 3);
+
+    s.client.toggle_auto_renew(&id, &false, &None);
+    assert_eq!(s.client.get_stream(&id).renewal_allowance.remaining_cycles, 0);
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 0);
+}
+
+#[test]
+fn test_renew_stream_by_subscriber() {
+    let s = Setup::new();
+    let id = s.create_renewable(None);
+
+    s.advance(DURATION);
+    let new_id = s.client.renew_stream(&id);
+
+    assert_eq!(s.env.auths()[0].0, s.subscriber);
+    assert_eq!(new_id, 2);
+    s.assert_balances(MINTED - 2 * AMOUNT, 0, 0, 2 * AMOUNT);
+
+    let old = s.client.get_stream(&id);
+    assert_eq!(old.status, StreamStatus::Completed);
+    assert!(!old.auto_renew);
+
+    let renewed = s.client.get_stream(&new_id);
+    assert_eq!(renewed.status, StreamStatus::Active);
+    assert_eq!(renewed.total_amount, AMOUNT);
+    assert_eq!(renewed.start_time, START + DURATION);
+    assert!(renewed.auto_renew);
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 1);
+
+    // The old stream's accrual can still be collected without disturbing the new one
+    let paid = s.client.withdraw(&id);
+    assert_eq!(paid, AMOUNT - fee_of(AMOUNT));
+    assert!(s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 1);
+    s.assert_balances(MINTED - 2 * AMOUNT, paid, fee_of(AMOUNT), AMOUNT);
+}
+
+#[test]
+fn test_renew_stream_by_keeper_within_allowance() {
+    let s = Setup::new();
+    let allowance = RenewalAllowance { remaining_cycles: 2, max_amount_per_cycle: AMOUNT };
+    let id = s.create_renewable(Some(allowance));
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 2 * AMOUNT);
+
+    s.advance(DURATION);
+    let second = s.client.renew_stream(&id);
+
+    // The retired stream still quotes what the creator can withdraw from it
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Completed);
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT - fee_of(AMOUNT));
+
+    // The subscriber did not sign the renewal; funds came from the approval
+    assert!(s.env.auths().iter().all(|(address, _)| *address != s.subscriber));
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), AMOUNT);
+    assert_eq!(s.client.get_stream(&second).renewal_allowance.remaining_cycles, 1);
+    s.assert_balances(MINTED - 2 * AMOUNT, 0, 0, 2 * AMOUNT);
+
+    s.advance(DURATION);
+    let third = s.client.renew_stream(&second);
+    assert!(s.env.auths().iter().all(|(address, _)| *address != s.subscriber));
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 0);
+    assert_eq!(s.client.get_stream(&third).renewal_allowance.remaining_cycles, 0);
+    s.assert_balances(MINTED - 3 * AMOUNT, 0, 0, 3 * AMOUNT);
+
+    // Allowance exhausted: the next renewal needs the subscriber again
+    s.advance(DURATION);
+    s.client.renew_stream(&third);
+    assert_eq!(s.env.auths()[0].0, s.subscriber);
+}
+
+#[test]
+fn test_keeper_renewal_fails_without_funds() {
+    let s = Setup::new();
+    let allowance = RenewalAllowance { remaining_cycles: 1, max_amount_per_cycle: AMOUNT };
+    let id = s.create_renewable(Some(allowance));
+
+    // Drain the subscriber so the allowance can't be honoured
+    let sink = Address::generate(&s.env);
+    s.token.transfer(&s.subscriber, &sink, &(MINTED - AMOUNT));
+
+    s.advance(DURATION);
+    assert_eq!(s.client.try_renew_stream(&id), Err(Ok(StreamError::RenewalFailed)));
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Active);
+}
+
+#[test]
+fn test_closing_streams_releases_renewal_approval() {
+    let s = Setup::new();
+    let allowance = RenewalAllowance { remaining_cycles: 2, max_amount_per_cycle: AMOUNT };
+    let cancelled = s.create_renewable(Some(allowance.clone()));
+    let other_creator = Address::generate(&s.env);
+    let expired = s.client.create_stream(
+        &s.subscriber, &other_creator, &s.token.address, &AMOUNT, &DURATION, &0, &true, &Some(allowance), &None,
+    );
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 4 * AMOUNT);
+
+    // Only the closed stream's budget is handed back
+    s.client.cancel(&cancelled);
+    assert_eq!(s.client.get_stream(&cancelled).renewal_allowance.remaining_cycles, 0);
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 2 * AMOUNT);
+
+    // A subscriber expiring their own stream signs for the approval too
+    s.env.mock_all_auths_allowing_non_root_auth();
+    s.advance(DURATION + DEFAULT_GRACE_PERIOD);
+    s.client.expire_stream(&expired);
+    assert_eq!(s.client.get_stream(&expired).renewal_allowance.remaining_cycles, 0);
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 0);
+}
+
+#[test]
+fn test_expiring_withdrawn_renewable_stream_releases_approval() {
+    let s = Setup::new();
+    s.env.mock_all_auths_allowing_non_root_auth();
+    let id = s.create_renewable(Some(RenewalAllowance { remaining_cycles: 3, max_amount_per_cycle: AMOUNT }));
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 3 * AMOUNT);
+
+    // A final withdrawal completes the stream but it can still be renewed
+    s.advance(DURATION);
+    s.client.withdraw(&id);
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Completed);
+    assert_eq!(s.client.try_expire_stream(&id), Err(Ok(StreamError::StreamNotEnded)));
+
+    // Once the grace period lapses the budget comes down
+    s.advance(DEFAULT_GRACE_PERIOD);
+    assert_eq!(s.client.expire_stream(&id), 0);
+    let stream = s.client.get_stream(&id);
+    assert!(!stream.auto_renew);
+    assert_eq!(stream.renewal_allowance.remaining_cycles, 0);
+    assert_eq!(s.token.allowance(&s.subscriber, &s.client.address), 0);
+    assert_eq!(s.client.try_expire_stream(&id), Err(Ok(StreamError::StreamNotActive)));
+    s.assert_balances(MINTED - AMOUNT, AMOUNT - fee_of(AMOUNT), fee_of(AMOUNT), 0);
+}
+
+#[test]
+fn test_renew_stream_rejects_ineligible_streams() {
+    let s = Setup::new();
+    let plain = s.create_default();
+
+    // Not yet ended
+    assert_eq!(s.client.try_renew_stream(&plain), Err(Ok(StreamError::StreamNotActive)));
+
+    // Auto-renew disabled
+    s.advance(DURATION);
+    assert_eq!(s.client.try_renew_stream(&plain), Err(Ok(StreamError::StreamNotActive)));
+
+    // Cancelled streams are never revived
+    s.client.cancel(&plain);
+    let renewable = s.create_renewable(None);
+    s.client.cancel(&renewable);
+    s.advance(DURATION);
+    assert_eq!(s.client.try_renew_stream(&renewable), Err(Ok(StreamError::StreamNotActive)));
+}
+
+#[test]
+fn test_renew_stream_after_grace_period_fails() {
+    let s = Setup::new();
+    let id = s.create_renewable(None);
+
+    s.advance(DURATION + DEFAULT_GRACE_PERIOD + 1);
+    assert_eq!(s.client.try_renew_stream(&id), Err(Ok(StreamError::InGracePeriod)));
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Active);
+}
+
+// === Expiry ===
+
+#[test]
+fn test_expire_stream_settles_creator() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(DURATION / 4);
+    let early = s.client.withdraw(&id);
+    assert_eq!(s.client.try_expire_stream(&id), Err(Ok(StreamError::StreamNotEnded)));
+
+    s.advance(DURATION);
+    let paid = s.client.expire_stream(&id);
+    assert_eq!(paid, AMOUNT * 3 / 4 - fee_of(AMOUNT * 3 / 4));
+    s.assert_balances(MINTED - AMOUNT, early + paid, fee_of(AMOUNT / 4) + fee_of(AMOUNT * 3 / 4), 0);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.status, StreamStatus::Completed);
+    assert_eq!(stream.withdrawn, AMOUNT);
+    assert!(!s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 0);
+    assert_eq!(s.client.try_expire_stream(&id), Err(Ok(StreamError::StreamNotActive)));
+}
+
+#[test]
+fn test_expire_stream_waits_for_renewal_grace() {
+    let s = Setup::new();
+    let id = s.create_renewable(None);
+
+    s.advance(DURATION);
+    assert_eq!(s.client.try_expire_stream(&id), Err(Ok(StreamError::StreamNotEnded)));
+
+    s.advance(DEFAULT_GRACE_PERIOD);
+    s.client.expire_stream(&id);
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.status, StreamStatus::Completed);
+    assert!(!stream.auto_renew);
+    assert_eq!(s.client.try_renew_stream(&id), Err(Ok(StreamError::StreamNotActive)));
+}
+
+#[test]
+fn test_sweep_expired_skips_running_streams() {
+    let s = Setup::new();
+    let ended = s.create_default();
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+
+    s.advance(DURATION);
+    let running = s.client.create_stream(
+        &other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+
+    let ids = soroban_sdk::vec![&s.env, ended, running, 99];
+    assert_eq!(s.client.sweep_expired(&ids), 1);
+    assert_eq!(s.client.get_stream(&ended).status, StreamStatus::Completed);
+    assert_eq!(s.client.get_stream(&running).status, StreamStatus::Active);
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 1);
+    assert_eq!(s.contract_balance(), AMOUNT);
+}
+
+// === Termination ===
+
+#[test]
+fn test_terminate_stream_full_refund_returns_remaining_balance() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(300);
+    let withdrawn = s.client.withdraw(&id);
+    s.advance(100);
+    let (creator_total, refund) = s.client.terminate_stream(&id, &TerminationMode::FullRefund, &3);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+
+    assert_eq!(creator_total, 0);
+    assert_eq!(refund, 700_000);
+    s.assert_balances(MINTED - 300_000, withdrawn, fee_of(300_000), 0);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.status, StreamStatus::Terminated);
+    assert_eq!(stream.termination_reason, 3);
+    assert!(!s.client.has_active_stream(&s.subscriber, &s.creator));
+    assert_eq!(s.client.get_active_subscriber_count(&s.creator), 0);
+}
+
+#[test]
+fn test_terminate_stream_settle_pays_accrued_share() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(300);
+    let withdrawn = s.client.withdraw(&id);
+    s.advance(100);
+    let (creator_total, refund) = s.client.terminate_stream(&id, &TerminationMode::Settle, &7);
+
+    assert_eq!(creator_total, 100_000);
+    assert_eq!(refund, 600_000);
+    s.assert_balances(
+        MINTED - 400_000,
+        withdrawn + 100_000 - fee_of(100_000),
+        fee_of(300_000) + fee_of(100_000),
+        0,
+    );
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.withdrawn, 400_000);
+    assert_eq!(stream.termination_reason, 7);
+}
+
+#[test]
+fn test_terminate_stream_twice_fails() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.terminate_stream(&id, &TerminationMode::Settle, &0);
+    assert_eq!(
+        s.client.try_terminate_stream(&id, &TerminationMode::FullRefund, &0),
+        Err(Ok(StreamError::AlreadyTerminated)),
+    );
+}
+
+// === Admin ===
+
+#[test]
+fn test_set_platform_wallet() {
+    let s = Setup::new();
+    let new_wallet = Address::generate(&s.env);
+
+    s.client.set_platform_wallet(&new_wallet);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+
+    let id = s.create_default();
+    assert_eq!(s.client.get_stream(&id).platform_wallet, new_wallet);
+
+    s.advance(DURATION);
+    s.client.withdraw(&id);
+    assert_eq!(s.balance(&new_wallet), fee_of(AMOUNT));
+    assert_eq!(s.balance(&s.platform), 0);
+}
+
+#[test]
+fn test_set_platform_fee() {
+    let s = Setup::new();
+
+    s.client.set_platform_fee(&500);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_fee_bps(), 500);
+
+    let id = s.create_default();
+    s.advance(DURATION);
+    let paid = s.client.withdraw(&id);
+    assert_eq!(paid, AMOUNT - AMOUNT * 500 / BPS_DENOMINATOR);
+    s.assert_balances(MINTED - AMOUNT, paid, AMOUNT * 500 / BPS_DENOMINATOR, 0);
+
+    assert_eq!(s.client.try_set_platform_fee(&1_001), Err(Ok(StreamError::InvalidFee)));
+    assert_eq!(s.client.try_set_platform_fee(&-1), Err(Ok(StreamError::InvalidFee)));
+}
+
+#[test]
+fn test_fee_change_does_not_affect_open_streams() {
+    let s = Setup::new();
+    let id = s.create_default();
+    assert_eq!(s.client.get_stream(&id).fee_bps, DEFAULT_PLATFORM_FEE_BPS);
+
+    s.advance(DURATION / 2);
+    s.client.set_platform_fee(&1_000);
+    s.advance(DURATION / 2);
+
+    let paid = s.client.withdraw(&id);
+    assert_eq!(paid, AMOUNT - fee_of(AMOUNT));
+    s.assert_balances(MINTED - AMOUNT, paid, fee_of(AMOUNT), 0);
+}
+
+#[test]
+fn test_renewal_snapshots_current_fee() {
+    let s = Setup::new();
+    let id = s.create_renewable(None);
+
+    s.client.set_platform_fee(&500);
+    s.advance(DURATION);
+    let new_id = s.client.renew_stream(&id);
+    assert_eq!(s.client.get_stream(&id).fee_bps, DEFAULT_PLATFORM_FEE_BPS);
+    assert_eq!(s.client.get_stream(&new_id).fee_bps, 500);
+
+    s.advance(DURATION);
+    let old_paid = s.client.withdraw(&id);
+    let new_paid = s.client.withdraw(&new_id);
+    assert_eq!(old_paid, AMOUNT - fee_of(AMOUNT));
+    assert_eq!(new_paid, AMOUNT - AMOUNT * 500 / BPS_DENOMINATOR);
+}
+
+#[test]
+fn test_zero_fee_pays_creator_in_full() {
+    let s = Setup::new();
+    s.client.set_platform_fee(&0);
+
+    let id = s.create_default();
+    s.advance(DURATION);
+    let paid = s.client.withdraw(&id);
+
+    assert_eq!(paid, AMOUNT);
+    s.assert_balances(MINTED - AMOUNT, AMOUNT, 0, 0);
+}
+
+#[test]
+fn test_set_grace_period() {
+    let s = Setup::new();
+
+    s.client.set_grace_period(&60);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_grace_period_seconds(), 60);
+
+    let id = s.create_renewable(None);
+    s.advance(DURATION + 61);
+    assert_eq!(s.client.try_renew_stream(&id), Err(Ok(StreamError::InGracePeriod)));
+}
+
+#[test]
+fn test_admin_transfer_requires_acceptance() {
+    let s = Setup::new();
+    let new_admin = Address::generate(&s.env);
+
+    s.client.propose_admin(&new_admin);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_pending_admin(), Some(new_admin.clone()));
+
+    // Rights don't move until the proposal is accepted
+    assert_eq!(s.client.get_admin(), s.admin);
+    s.client.set_grace_period(&60);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+
+    s.client.accept_admin();
+    assert_eq!(s.env.auths()[0].0, new_admin);
+    assert_eq!(s.client.get_admin(), new_admin);
+    assert_eq!(s.client.get_pending_admin(), None);
+
+    s.client.set_grace_period(&120);
+    assert_eq!(s.env.auths()[0].0, new_admin);
+}
+
+#[test]
+fn test_cancel_admin_proposal() {
+    let s = Setup::new();
+    let new_admin = Address::generate(&s.env);
+
+    s.client.propose_admin(&new_admin);
+    s.client.cancel_admin_proposal();
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_pending_admin(), None);
+
+    assert_eq!(s.client.try_accept_admin(), Err(Ok(StreamError::NoPendingAdmin)));
+    assert_eq!(s.client.try_cancel_admin_proposal(), Err(Ok(StreamError::NoPendingAdmin)));
+    assert_eq!(s.client.get_admin(), s.admin);
+}
+
+#[test]
+fn test_new_proposal_replaces_previous_one() {
+    let s = Setup::new();
+    let typo = Address::generate(&s.env);
+    let intended = Address::generate(&s.env);
+
+    s.client.propose_admin(&typo);
+    s.client.propose_admin(&intended);
+    assert_eq!(s.client.get_pending_admin(), Some(intended.clone()));
+
+    s.client.accept_admin();
+    assert_eq!(s.env.auths()[0].0, intended);
+    assert_eq!(s.client.get_admin(), intended);
+}
+
+// === Fee policy ===
+
+#[test]
+fn test_creator_fee_override_applies_to_new_streams() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.set_creator_fee_override(&s.creator, &500);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_creator_fee_bps(&s.creator, &s.token.address), 500);
+    let other = Address::generate(&s.env);
+    assert_eq!(s.client.get_creator_fee_bps(&other, &s.token.address), DEFAULT_PLATFORM_FEE_BPS);
+
+    let tier = s.create_tier();
+    let new_id = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &tier, &None);
+    assert_eq!(s.client.get_stream(&id).fee_bps, DEFAULT_PLATFORM_FEE_BPS);
+    assert_eq!(s.client.get_stream(&new_id).fee_bps, 500);
+
+    s.advance(DURATION);
+    assert_eq!(s.client.withdraw(&id), AMOUNT - fee_of(AMOUNT));
+    assert_eq!(s.client.withdraw(&new_id), AMOUNT - AMOUNT * 500 / BPS_DENOMINATOR);
+    assert_eq!(s.balance(&s.platform), fee_of(AMOUNT) + AMOUNT * 500 / BPS_DENOMINATOR);
+
+    // The override wins over later global fee changes until cleared
+    s.client.set_platform_fee(&700);
+    assert_eq!(s.client.get_creator_fee_bps(&s.creator, &s.token.address), 500);
+    s.client.clear_creator_fee_override(&s.creator);
+    assert_eq!(s.client.get_creator_fee_bps(&s.creator, &s.token.address), 700);
+
+    assert_eq!(s.client.try_set_creator_fee_override(&s.creator, &1_001), Err(Ok(StreamError::InvalidFee)));
+    assert_eq!(s.client.try_set_creator_fee_override(&s.creator, &-1), Err(Ok(StreamError::InvalidFee)));
+}
+
+/// Fee rate reported by the last withdrawal event
+fn withdrawal_fee_bps(s: &Setup) -> i128 {
+    let (_, _, data) = s.env.events().all().last().unwrap();
+    let (_, _, _, fee_bps, _) = <(u64, i128, i128, i128, Address)>::try_from_val(&s.env, &data).unwrap();
+    fee_bps
+}
+
+#[test]
+fn test_volume_tier_lowers_fee_on_running_stream() {
+    let s = Setup::new();
+    let tiers = vec![
+        &s.env,
+        VolumeFeeTier { min_volume: AMOUNT / 2, fee_bps: 100 },
+        VolumeFeeTier { min_volume: AMOUNT * 2, fee_bps: 0 },
+    ];
+    s.client.set_volume_fee_tiers(&s.token.address, &tiers);
+    assert_eq!(s.client.get_volume_fee_tiers(&s.token.address), tiers);
+
+    let id = s.create_default();
+    s.advance(DURATION / 2);
+    let half = AMOUNT / 2;
+    assert_eq!(s.client.withdraw(&id), half - fee_of(half));
+    assert_eq!(withdrawal_fee_bps(&s), DEFAULT_PLATFORM_FEE_BPS);
+    assert_eq!(s.client.get_creator_volume(&s.creator, &s.token.address), half);
+    assert_eq!(s.client.get_creator_fee_bps(&s.creator, &s.token.address), 100);
+
+    // The rest of the stream pays the tier fee, not the locked-in one
+    s.advance(DURATION / 2);
+    assert_eq!(s.client.get_withdrawable(&id), half - half * 100 / BPS_DENOMINATOR);
+    assert_eq!(s.client.withdraw(&id), half - half * 100 / BPS_DENOMINATOR);
+    assert_eq!(withdrawal_fee_bps(&s), 100);
+    assert_eq!(s.client.get_stream(&id).fee_bps, DEFAULT_PLATFORM_FEE_BPS);
+    s.assert_balances(
+        MINTED - AMOUNT,
+        AMOUNT - fee_of(half) - half * 100 / BPS_DENOMINATOR,
+        fee_of(half) + half * 100 / BPS_DENOMINATOR,
+        0,
+    );
+
+    // A tier never raises a lower negotiated fee
+    s.client.set_creator_fee_override(&s.creator, &50);
+    assert_eq!(s.client.get_creator_fee_bps(&s.creator, &s.token.address), 50);
+
+    s.client.set_volume_fee_tiers(&s.token.address, &vec![&s.env]);
+    assert_eq!(s.client.get_volume_fee_tiers(&s.token.address).len(), 0);
+    s.client.clear_creator_fee_override(&s.creator);
+    assert_eq!(s.client.get_creator_fee_bps(&s.creator, &s.token.address), DEFAULT_PLATFORM_FEE_BPS);
+}
+
+#[test]
+fn test_set_volume_fee_tiers_rejects_invalid_schedules() {
+    let s = Setup::new();
+    let tier = |min_volume: i128, fee_bps: i128| VolumeFeeTier { min_volume, fee_bps };
+
+    let cases = [
+        (vec![&s.env, tier(0, 100)], StreamError::InvalidAmount),
+        (vec![&s.env, tier(AMOUNT, 100), tier(AMOUNT, 50)], StreamError::InvalidAmount),
+        (vec![&s.env, tier(AMOUNT, 1_001)], StreamError::InvalidFee),
+        (vec![&s.env, tier(AMOUNT, -1)], StreamError::InvalidFee),
+        (vec![&s.env, tier(AMOUNT, 100), tier(AMOUNT * 2, 150)], StreamError::InvalidFee),
+    ];
+    for (tiers, error) in cases {
+        assert_eq!(s.client.try_set_volume_fee_tiers(&s.token.address, &tiers), Err(Ok(error)));
+    }
+    assert_eq!(s.client.get_volume_fee_tiers(&s.token.address).len(), 0);
+}
+
+// === Token allowlist ===
+
+#[test]
+fn test_create_stream_rejects_unlisted_token() {
+    let s = Setup::new();
+    let other = s.env.register_stellar_asset_contract_v2(Address::generate(&s.env));
+    StellarAssetClient::new(&s.env, &other.address()).mint(&s.subscriber, &AMOUNT);
+
+    let result = s.client.try_create_stream(
+        &s.subscriber, &s.creator, &other.address(), &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+    assert_eq!(result, Err(Ok(StreamError::TokenNotAllowed)));
+    assert_eq!(s.client.get_allowed_tokens(), soroban_sdk::vec![&s.env, s.token.address.clone()]);
+}
+
+#[test]
+fn test_token_limits_apply_to_create_and_extend() {
+    let s = Setup::new();
+    let limits = TokenConfig { min_deposit: 1_000, min_duration: 60, max_deposit: Some(AMOUNT) };
+    s.client.set_token_config(&s.token.address, &limits);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_token_config(&s.token.address), Some(limits));
+
+    let create = |amount: i128, duration: u64| {
+        s.client.try_create_stream(
+            &s.subscriber, &s.creator, &s.token.address, &amount, &duration, &0, &false, &None, &None,
+        )
+    };
+    assert_eq!(create(999, DURATION), Err(Ok(StreamError::InvalidAmount)));
+    assert_eq!(create(AMOUNT + 1, DURATION), Err(Ok(StreamError::InvalidAmount)));
+    assert_eq!(create(AMOUNT, 59), Err(Ok(StreamError::InvalidDuration)));
+
+    let id = create(AMOUNT / 2, DURATION).unwrap().unwrap();
+
+    // The cap covers the stream's running total, not each deposit
+    assert_eq!(s.client.try_extend_stream(&id, &(AMOUNT / 2 + 1), &10), Err(Ok(StreamError::InvalidAmount)));
+    s.client.extend_stream(&id, &(AMOUNT / 2), &10);
+    assert_eq!(s.client.get_stream(&id).total_amount, AMOUNT);
+}
+
+#[test]
+fn test_set_token_config_rejects_inconsistent_limits() {
+    let s = Setup::new();
+    let inverted = TokenConfig { min_deposit: 500, min_duration: 0, max_deposit: Some(100) };
+    assert_eq!(s.client.try_set_token_config(&s.token.address, &inverted), Err(Ok(StreamError::InvalidAmount)));
+
+    let negative = TokenConfig { min_deposit: -1, min_duration: 0, max_deposit: None };
+    assert_eq!(s.client.try_set_token_config(&s.token.address, &negative), Err(Ok(StreamError::InvalidAmount)));
+
+    assert_eq!(s.client.get_token_config(&s.token.address), Some(open_limits()));
+}
+
+#[test]
+fn test_removed_token_only_blocks_new_funds() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.remove_token(&s.token.address);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_token_config(&s.token.address), None);
+    assert_eq!(s.client.get_allowed_tokens().len(), 0);
+    assert_eq!(s.client.try_remove_token(&s.token.address), Err(Ok(StreamError::TokenNotAllowed)));
+
+    assert_eq!(s.client.try_extend_stream(&id, &10, &10), Err(Ok(StreamError::TokenNotAllowed)));
+
+    // Escrowed funds still flow out
+    s.advance(DURATION / 2);
+    let paid = s.client.withdraw(&id);
+    let (_, refund) = s.client.cancel(&id);
+    assert_eq!(paid + fee_of(AMOUNT / 2) + refund, AMOUNT);
+    assert_eq!(s.contract_balance(), 0);
+}
+
+// === Stream queries ===
+
+#[test]
+fn test_stream_queries_are_paginated() {
+    let s = Setup::new();
+    s.fund(&s.subscriber, MINTED);
+
+    let mut ids = std::vec::Vec::new();
+    for _ in 0..5 {
+        let creator = Address::generate(&s.env);
+        ids.push(s.client.create_stream(
+            &s.subscriber, &creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+        ));
+    }
+    s.client.cancel(&ids[1]);
+
+    assert_eq!(s.client.get_subscriber_stream_count(&s.subscriber), 5);
+    assert_eq!(
+        s.client.get_subscriber_streams(&s.subscriber, &1, &2),
+        soroban_sdk::vec![&s.env, ids[1], ids[2]],
+    );
+    assert_eq!(
+        s.client.get_subscriber_streams(&s.subscriber, &3, &10),
+        soroban_sdk::vec![&s.env, ids[3], ids[4]],
+    );
+    assert_eq!(s.client.get_subscriber_streams(&s.subscriber, &7, &10).len(), 0);
+
+    // The closed stream's slot was filled by the last active one
+    assert_eq!(s.client.get_subscriber_active_count(&s.subscriber), 4);
+    assert_eq!(
+        s.client.get_active_subscriber_streams(&s.subscriber, &0, &3),
+        soroban_sdk::vec![&s.env, ids[0], ids[4], ids[2]],
+    );
+    assert_eq!(
+        s.client.get_active_subscriber_streams(&s.subscriber, &3, &3),
+        soroban_sdk::vec![&s.env, ids[3]],
+    );
+}
+
+#[test]
+fn test_page_size_is_capped() {
+    let s = Setup::new();
+    s.fund(&s.subscriber, AMOUNT * 120);
+    // Building the history is not what this test measures
+    s.env.budget().reset_unlimited();
+
+    for _ in 0..(MAX_PAGE_SIZE + 5) {
+        s.client.create_stream(
+            &s.subscriber, &Address::generate(&s.env), &s.token.address, &1_000, &DURATION, &0, &false, &None, &None,
+        );
+    }
+
+    assert_eq!(s.client.get_subscriber_stream_count(&s.subscriber), MAX_PAGE_SIZE + 5);
+    assert_eq!(s.client.get_subscriber_streams(&s.subscriber, &0, &u32::MAX).len(), MAX_PAGE_SIZE);
+    assert_eq!(s.client.get_active_creator_streams(&s.creator, &0, &10).len(), 0);
+    assert_eq!(s.client.get_creator_stream_count(&s.creator), 0);
+}
+
+// === Tier catalogue ===
+
+#[test]
+fn test_subscribe_to_tier_uses_catalogue_terms() {
+    let s = Setup::new();
+    let tier_id = s.create_tier();
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    assert_eq!(tier_id, 1);
+
+    let id = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &tier_id, &None);
+    assert_eq!(s.env.auths()[0].0, s.subscriber);
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.tier_id, tier_id);
+    assert_eq!(stream.token, s.token.address);
+    assert_eq!(stream.total_amount, AMOUNT);
+    assert_eq!(stream.end_time, START + DURATION);
+}
+
+#[test]
+fn test_create_stream_must_match_claimed_tier() {
+    let s = Setup::new();
+    let tier_id = s.create_tier();
+
+    let create = |amount: i128, duration: u64, tier: u32| {
+        s.client.try_create_stream(
+            &s.subscriber, &s.creator, &s.token.address, &amount, &duration, &tier, &false, &None, &None,
+        )
+    };
+    assert_eq!(create(AMOUNT / 2, DURATION, tier_id), Err(Ok(StreamError::TierMismatch)));
+    assert_eq!(create(AMOUNT, DURATION * 2, tier_id), Err(Ok(StreamError::TierMismatch)));
+    assert_eq!(create(AMOUNT, DURATION, 7), Err(Ok(StreamError::TierNotFound)));
+    s.assert_balances(MINTED, 0, 0, 0);
+
+    let id = create(AMOUNT, DURATION, tier_id).unwrap().unwrap();
+    assert_eq!(s.client.get_stream(&id).tier_id, tier_id);
+}
+
+#[test]
+fn test_tier_crud() {
+    let s = Setup::new();
+    let first = s.create_tier();
+    let second = s.client.create_tier(&s.creator, &s.token.address, &(AMOUNT * 3), &(DURATION * 2));
+    assert_eq!(s.client.get_creator_tiers(&s.creator).len(), 2);
+
+    s.client.update_tier(&s.creator, &first, &(AMOUNT * 2), &DURATION, &false);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    let tier = s.client.get_tier(&s.creator, &first);
+    assert_eq!(tier.price, AMOUNT * 2);
+    assert!(!tier.active);
+    assert_eq!(
+        s.client.try_subscribe_to_tier(&s.subscriber, &s.creator, &first, &None),
+        Err(Ok(StreamError::TierNotActive)),
+    );
+
+    s.client.remove_tier(&s.creator, &first);
+    assert_eq!(s.client.try_get_tier(&s.creator, &first), Err(Ok(StreamError::TierNotFound)));
+    assert_eq!(s.client.try_remove_tier(&s.creator, &first), Err(Ok(StreamError::TierNotFound)));
+
+    let tiers = s.client.get_creator_tiers(&s.creator);
+    assert_eq!(tiers.len(), 1);
+    assert_eq!(tiers.get(0).unwrap().id, second);
+
+    // IDs are never reused
+    assert_eq!(s.create_tier(), 3);
+}
+
+#[test]
+fn test_has_active_tier_survives_tier_removal() {
+    let s = Setup::new();
+    let tier = s.create_tier();
+    let id = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &tier, &None);
+    assert!(s.client.has_active_tier(&s.subscriber, &s.creator, &tier));
+
+    // The subscription runs on after the tier leaves the catalogue
+    s.client.remove_tier(&s.creator, &tier);
+    assert!(s.client.has_active_tier(&s.subscriber, &s.creator, &tier));
+
+    s.client.cancel(&id);
+    assert!(!s.client.has_active_tier(&s.subscriber, &s.creator, &tier));
+}
+
+#[test]
+fn test_create_tier_validates_terms() {
+    let s = Setup::new();
+    let other = s.env.register_stellar_asset_contract_v2(Address::generate(&s.env));
+
+    assert_eq!(
+        s.client.try_create_tier(&s.creator, &other.address(), &AMOUNT, &DURATION),
+        Err(Ok(StreamError::TokenNotAllowed)),
+    );
+    assert_eq!(
+        s.client.try_create_tier(&s.creator, &s.token.address, &0, &DURATION),
+        Err(Ok(StreamError::InvalidAmount)),
+    );
+    assert_eq!(
+        s.client.try_create_tier(&s.creator, &s.token.address, &AMOUNT, &0),
+        Err(Ok(StreamError::InvalidDuration)),
+    );
+    assert_eq!(s.client.get_creator_tiers(&s.creator).len(), 0);
+}
+
+#[test]
+fn test_tiered_extension_must_keep_tier_rate() {
+    let s = Setup::new();
+    let tier_id = s.create_tier();
+    let id = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &tier_id, &None);
+
+    assert_eq!(s.client.try_extend_stream(&id, &AMOUNT, &(DURATION * 2)), Err(Ok(StreamError::TierMismatch)));
+
+    s.client.extend_stream(&id, &(AMOUNT / 2), &(DURATION / 2));
+    assert_eq!(s.client.get_stream(&id).total_amount, AMOUNT + AMOUNT / 2);
+}
+
+#[test]
+fn test_tiered_renewal_uses_current_tier_price() {
+    let s = Setup::new();
+    let tier_id = s.create_tier();
+    let id = s.client.create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &tier_id, &true, &None, &None,
+    );
+
+    s.client.update_tier(&s.creator, &tier_id, &(AMOUNT * 2), &(DURATION * 3), &true);
+
+    s.advance(DURATION);
+    let new_id = s.client.renew_stream(&id);
+    let renewed = s.client.get_stream(&new_id);
+    assert_eq!(renewed.total_amount, AMOUNT * 2);
+    assert_eq!(renewed.end_time, START + DURATION + DURATION * 3);
+
+    // A deactivated tier stops further renewals
+    s.client.update_tier(&s.creator, &tier_id, &(AMOUNT * 2), &(DURATION * 3), &false);
+    s.advance(DURATION * 3);
+    assert_eq!(s.client.try_renew_stream(&new_id), Err(Ok(StreamError::TierNotActive)));
+}
+
+#[test]
+fn test_change_tier_upgrade_pulls_only_the_difference() {
+    let s = Setup::new();
+    let basic = s.create_tier();
+    let premium = s.client.create_tier(&s.creator, &s.token.address, &(AMOUNT * 3), &(DURATION * 2));
+    let id = s.client.create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &basic, &true, &None, &None,
+    );
+
+    // A quarter has streamed, the rest is credited towards the new tier
+    s.advance(DURATION / 4);
+    let charge = s.client.change_tier(&id, &premium, &(AMOUNT * 3), &(DURATION * 2));
+    assert_eq!(s.env.auths()[0].0, s.subscriber);
+    assert_eq!(charge, AMOUNT * 3 - AMOUNT * 3 / 4);
+    s.assert_balances(MINTED - AMOUNT - charge, 0, 0, AMOUNT + charge);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.tier_id, premium);
+    assert_eq!(stream.total_amount, AMOUNT / 4 + AMOUNT * 3);
+    assert_eq!(stream.end_time, START + DURATION / 4 + DURATION * 2);
+    assert!(stream.auto_renew);
+    assert_eq!(s.client.get_active_stream(&s.subscriber, &s.creator, &premium, &s.token.address), Some(id));
+    assert_eq!(s.client.get_active_stream(&s.subscriber, &s.creator, &basic, &s.token.address), None);
+    assert!(s.client.has_active_tier(&s.subscriber, &s.creator, &premium));
+    assert!(!s.client.has_active_tier(&s.subscriber, &s.creator, &basic));
+
+    // Past accrual survives the change and everything streams out
+    s.advance(DURATION * 2);
+    let total = AMOUNT / 4 + AMOUNT * 3;
+    s.client.withdraw(&id);
+    s.assert_balances(MINTED - AMOUNT - charge, total - fee_of(total), fee_of(total), 0);
+}
+
+#[test]
+fn test_change_tier_downgrade_refunds_the_excess() {
+    let s = Setup::new();
+    let basic = s.create_tier();
+    let premium = s.client.create_tier(&s.creator, &s.token.address, &(AMOUNT * 3), &(DURATION * 2));
+    let id = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &premium, &None);
+
+    s.advance(DURATION / 2);
+    let charge = s.client.change_tier(&id, &basic, &AMOUNT, &DURATION);
+    let accrued = AMOUNT * 3 / 4;
+    assert_eq!(charge, AMOUNT - (AMOUNT * 3 - accrued));
+    s.assert_balances(MINTED - AMOUNT * 3 - charge, 0, 0, accrued + AMOUNT);
+
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.tier_id, basic);
+    assert_eq!(stream.total_amount, accrued + AMOUNT);
+    assert_eq!(stream.duration_seconds, DURATION);
+}
+
+#[test]
+fn test_renewal_after_change_tier_charges_one_period() {
+    let s = Setup::new();
+    let id = s.create_renewable(None);
+
+    // Switch halfway to custom terms of AMOUNT per DURATION
+    s.advance(DURATION / 2);
+    s.client.change_tier(&id, &0, &AMOUNT, &DURATION);
+    assert_eq!(s.client.get_stream(&id).total_amount, AMOUNT / 2 + AMOUNT);
+    assert_eq!(s.client.get_stream(&id).cycle_amount, AMOUNT);
+
+    s.advance(DURATION);
+    let before = s.balance(&s.subscriber);
+    let renewed = s.client.renew_stream(&id);
+    assert_eq!(before - s.balance(&s.subscriber), AMOUNT);
+    let stream = s.client.get_stream(&renewed);
+    assert_eq!(stream.total_amount, AMOUNT);
+    assert_eq!(stream.duration_seconds, DURATION);
+
+    // Extensions add to the period that renews
+    s.client.extend_stream(&renewed, &(AMOUNT / 2), &(DURATION / 2));
+    s.advance(DURATION + DURATION / 2);
+    let before = s.balance(&s.subscriber);
+    let third = s.client.renew_stream(&renewed);
+    assert_eq!(before - s.balance(&s.subscriber), AMOUNT + AMOUNT / 2);
+    assert_eq!(s.client.get_stream(&third).duration_seconds, DURATION + DURATION / 2);
+}
+
+#[test]
+fn test_change_tier_rejects_invalid_targets() {
+    let s = Setup::new();
+    let basic = s.create_tier();
+    let premium = s.client.create_tier(&s.creator, &s.token.address, &(AMOUNT * 3), &(DURATION * 2));
+    let id = s.client.subscribe_to_tier(&s.subscriber, &s.creator, &basic, &None);
+
+    assert_eq!(s.client.try_change_tier(&id, &premium, &AMOUNT, &DURATION), Err(Ok(StreamError::TierMismatch)));
+    assert_eq!(s.client.try_change_tier(&id, &9, &AMOUNT, &DURATION), Err(Ok(StreamError::TierNotFound)));
+    assert_eq!(s.client.try_change_tier(&id, &0, &0, &DURATION), Err(Ok(StreamError::InvalidAmount)));
+
+    // The subscriber already holds the target tier
+    s.client.subscribe_to_tier(&s.subscriber, &s.creator, &premium, &None);
+    assert_eq!(
+        s.client.try_change_tier(&id, &premium, &(AMOUNT * 3), &(DURATION * 2)),
+        Err(Ok(StreamError::StreamAlreadyExists)),
+    );
+
+    s.advance(DURATION);
+    assert_eq!(s.client.try_change_tier(&id, &0, &AMOUNT, &DURATION), Err(Ok(StreamError::StreamNotActive)));
+}
+
+// === Payout addresses ===
+
+#[test]
+fn test_payouts_go_to_registered_address() {
+    let s = Setup::new();
+    let treasury = Address::generate(&s.env);
+    assert_eq!(s.client.get_payout_address(&s.creator), s.creator);
+    s.client.set_payout_address(&s.creator, &treasury);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    assert_eq!(s.client.get_payout_address(&s.creator), treasury);
+
+    let id = s.create_default();
+    s.advance(DURATION / 4);
+    let earned = AMOUNT / 4;
+    s.client.withdraw(&id);
+    assert_eq!(s.balance(&treasury), earned - fee_of(earned));
+    assert_eq!(s.balance(&s.creator), 0);
+
+    // The event reports where the payout went
+    let (_, _, data) = s.env.events().all().last().unwrap();
+    let (_, _, _, _, destination) = <(u64, i128, i128, i128, Address)>::try_from_val(&s.env, &data).unwrap();
+    assert_eq!(destination, treasury);
+
+    // Settlements on cancel follow the same route
+    s.advance(DURATION / 4);
+    s.client.cancel(&id);
+    assert_eq!(s.balance(&treasury), AMOUNT / 2 - fee_of(earned) * 2);
+    assert_eq!(s.balance(&s.creator), 0);
+}
+
+#[test]
+fn test_clear_payout_address_pays_creator_again() {
+    let s = Setup::new();
+    let treasury = Address::generate(&s.env);
+    s.client.set_payout_address(&s.creator, &treasury);
+    s.client.clear_payout_address(&s.creator);
+    assert_eq!(s.client.get_payout_address(&s.creator), s.creator);
+
+    let id = s.create_default();
+    s.advance(DURATION);
+    assert_eq!(s.client.withdraw_all(&s.creator, &0, &MAX_PAGE_SIZE), AMOUNT - fee_of(AMOUNT));
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Completed);
+    s.assert_balances(MINTED - AMOUNT, AMOUNT - fee_of(AMOUNT), fee_of(AMOUNT), 0);
+    assert_eq!(s.balance(&treasury), 0);
+}
+
+#[test]
+fn test_payout_address_cannot_be_contract() {
+    let s = Setup::new();
+    assert_eq!(
+        s.client.try_set_payout_address(&s.creator, &s.client.address),
+        Err(Ok(StreamError::InvalidPayoutAddress)),
+    );
+}
+
+// === Revenue splits ===
+
+fn share(recipient: &Address, bps: u32) -> SplitShare {
+    SplitShare { recipient: recipient.clone(), bps }
+}
+
+#[test]
+fn test_withdraw_distributes_by_split() {
+    let s = Setup::new();
+    let alice = Address::generate(&s.env);
+    s.client.set_revenue_split(&s.creator, &vec![&s.env, share(&s.creator, 6_000), share(&alice, 4_000)], &MAX_PAGE_SIZE);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    assert_eq!(s.client.get_revenue_split(&s.creator).len(), 2);
+
+    let id = s.create_default();
+    s.advance(DURATION / 4);
+    let net = AMOUNT / 4 - fee_of(AMOUNT / 4);
+    assert_eq!(s.client.withdraw(&id), net);
+    assert_eq!(s.balance(&s.creator), net * 6 / 10);
+    assert_eq!(s.balance(&alice), net - net * 6 / 10);
+    assert_eq!(s.balance(&s.platform), fee_of(AMOUNT / 4));
+}
+
+#[test]
+fn test_split_change_only_applies_to_future_accrual() {
+    let s = Setup::new();
+    let alice = Address::generate(&s.env);
+    let id = s.create_default();
+
+    // What accrued before the split is settled to the creator alone
+    s.advance(DURATION / 4);
+    s.client.set_revenue_split(&s.creator, &vec![&s.env, share(&alice, 10_000)], &MAX_PAGE_SIZE);
+    let net = AMOUNT / 4 - fee_of(AMOUNT / 4);
+    assert_eq!(s.balance(&s.creator), net);
+    assert_eq!(s.client.get_stream(&id).withdrawn, AMOUNT / 4);
+
+    s.advance(DURATION / 4);
+    s.client.withdraw(&id);
+    assert_eq!(s.balance(&alice), net);
+
+    // Clearing settles under the split being removed
+    s.advance(DURATION / 4);
+    s.client.clear_revenue_split(&s.creator, &MAX_PAGE_SIZE);
+    assert_eq!(s.balance(&alice), net * 2);
+    assert_eq!(s.client.get_revenue_split(&s.creator).len(), 0);
+
+    s.advance(DURATION / 4);
+    s.client.withdraw(&id);
+    assert_eq!(s.balance(&s.creator), net * 2);
+}
+
+#[test]
+fn test_settlements_follow_split_and_payout_address() {
+    let s = Setup::new();
+    let bob = Address::generate(&s.env);
+    let treasury = Address::generate(&s.env);
+    s.client.set_payout_address(&s.creator, &treasury);
+    s.client.set_revenue_split(&s.creator, &vec![&s.env, share(&s.creator, 5_000), share(&bob, 5_000)], &MAX_PAGE_SIZE);
+
+    let id = s.create_default();
+    s.advance(DURATION / 2);
+    s.client.terminate_stream(&id, &TerminationMode::Settle, &0);
+    let net = AMOUNT / 2 - fee_of(AMOUNT / 2);
+    assert_eq!(s.balance(&treasury), net / 2);
+    assert_eq!(s.balance(&bob), net - net / 2);
+    assert_eq!(s.balance(&s.creator), 0);
+    assert_eq!(s.balance(&s.subscriber), MINTED - AMOUNT / 2);
+}
+
+#[test]
+fn test_split_change_settles_a_page_at_a_time() {
+    let s = Setup::new();
+    let alice = Address::generate(&s.env);
+    let mut ids = std::vec::Vec::new();
+    for _ in 0..3 {
+        let subscriber = Address::generate(&s.env);
+        s.fund(&subscriber, MINTED);
+        ids.push(s.client.create_stream(
+            &subscriber, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+        ));
+    }
+    s.advance(DURATION / 4);
+    let split = vec![&s.env, share(&alice, 10_000)];
+
+    // The split waits until every active stream has been settled
+    assert_eq!(s.client.set_revenue_split(&s.creator, &split, &2), 1);
+    assert_eq!(s.client.get_revenue_split(&s.creator).len(), 0);
+    assert_eq!(s.client.get_stream(&ids[0]).withdrawn, 0);
+    assert_eq!(s.client.get_stream(&ids[2]).withdrawn, AMOUNT / 4);
+
+    // Different shares start the walk over
+    let other = vec![&s.env, share(&s.creator, 5_000), share(&alice, 5_000)];
+    assert_eq!(s.client.set_revenue_split(&s.creator, &other, &1), 2);
+    assert_eq!(s.client.set_revenue_split(&s.creator, &split, &2), 1);
+    assert_eq!(s.client.set_revenue_split(&s.creator, &split, &2), 0);
+    assert_eq!(s.client.get_revenue_split(&s.creator), split);
+
+    // Everything accrued before the switch went to the creator
+    let net = AMOUNT / 4 - fee_of(AMOUNT / 4);
+    assert_eq!(s.balance(&s.creator), net * 3);
+    assert_eq!(s.balance(&alice), 0);
+    for id in ids.iter() {
+        assert_eq!(s.client.get_stream(id).withdrawn, AMOUNT / 4);
+    }
+
+    s.advance(DURATION / 4);
+    assert_eq!(s.client.clear_revenue_split(&s.creator, &1), 2);
+    assert_eq!(s.client.get_revenue_split(&s.creator).len(), 1);
+    assert_eq!(s.client.clear_revenue_split(&s.creator, &2), 0);
+    assert_eq!(s.client.get_revenue_split(&s.creator).len(), 0);
+    assert_eq!(s.balance(&alice), net * 3);
+}
+
+#[test]
+fn test_set_revenue_split_validates_shares() {
+    let s = Setup::new();
+    let alice = Address::generate(&s.env);
+    let invalid = |shares: soroban_sdk::Vec<SplitShare>| {
+        assert_eq!(s.client.try_set_revenue_split(&s.creator, &shares, &MAX_PAGE_SIZE), Err(Ok(StreamError::InvalidSplit)));
+    };
+
+    invalid(vec![&s.env]);
+    invalid(vec![&s.env, share(&s.creator, 5_000), share(&alice, 4_000)]);
+    invalid(vec![&s.env, share(&s.creator, 10_000), share(&alice, 0)]);
+    invalid(vec![&s.env, share(&alice, 5_000), share(&alice, 5_000)]);
+    invalid(vec![&s.env, share(&s.client.address, 10_000)]);
+
+    let mut crowd = vec![&s.env];
+    for _ in 0..11 {
+        crowd.push_back(share(&Address::generate(&s.env), 1));
+    }
+    invalid(crowd);
+    assert_eq!(s.client.get_revenue_split(&s.creator).len(), 0);
+}
+
+// === Referrals ===
+
+impl Setup<'_> {
+    fn try_create_referred(&self, referrer: &Address, auto_renew: bool) -> Result<u64, StreamError> {
+        self.client
+            .try_create_stream(
+                &self.subscriber,
+                &self.creator,
+                &self.token.address,
+                &AMOUNT,
+                &DURATION,
+                &0,
+                &auto_renew,
+                &None,
+                &Some(StreamOptions { referrer: Some(referrer.clone()), ..StreamOptions::default() }),
+            )
+            .map(|id| id.unwrap())
+            .map_err(|err| err.unwrap())
+    }
+}
+
+#[test]
+fn test_referrer_earns_share_of_platform_fee() {
+    let s = Setup::new();
+    let referrer = Address::generate(&s.env);
+    s.client.set_referral_config(&ReferralConfig { fee_share_bps: 5_000, max_revenue_share_bps: 0 });
+    assert_eq!(s.env.auths()[0].0, s.admin);
+
+    let id = s.try_create_referred(&referrer, false).unwrap();
+    assert_eq!(s.client.get_stream(&id).referrer, Some(referrer.clone()));
+
+    s.advance(DURATION / 4);
+    let fee = fee_of(AMOUNT / 4);
+    assert_eq!(s.client.withdraw(&id), AMOUNT / 4 - fee);
+    assert_eq!(s.balance(&referrer), fee / 2);
+    assert_eq!(s.balance(&s.platform), fee - fee / 2);
+    assert_eq!(s.client.get_referral_earnings(&referrer, &s.token.address), fee / 2);
+
+    // Terms are fixed for the stream's life
+    s.client.set_referral_config(&ReferralConfig { fee_share_bps: 0, max_revenue_share_bps: 0 });
+    s.advance(DURATION / 4);
+    s.client.cancel(&id);
+    assert_eq!(s.client.get_referral_earnings(&referrer, &s.token.address), fee);
+}
+
+#[test]
+fn test_referrer_earns_revenue_share_creator_opts_into() {
+    let s = Setup::new();
+    let referrer = Address::generate(&s.env);
+    s.client.set_referral_config(&ReferralConfig { fee_share_bps: 0, max_revenue_share_bps: 1_000 });
+    s.client.set_referral_share(&s.creator, &1_000);
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    assert_eq!(s.client.get_creator_referral_share(&s.creator), 1_000);
+
+    let id = s.try_create_referred(&referrer, false).unwrap();
+    s.advance(DURATION / 4);
+
+    // Quotes match what the creator is actually paid
+    let quarter = AMOUNT / 4 - fee_of(AMOUNT / 4);
+    let quoted = s.client.get_withdrawable(&id);
+    assert_eq!(quoted, quarter - quarter / 10);
+    assert_eq!(s.client.get_total_accrued(&s.creator, &0, &MAX_PAGE_SIZE), quoted);
+    assert_eq!(s.client.withdraw(&id), quoted);
+    assert_eq!(s.balance(&s.creator), quoted);
+
+    s.advance(DURATION / 4);
+    s.client.cancel(&id);
+
+    let net = AMOUNT / 2 - fee_of(AMOUNT / 2);
+    assert_eq!(s.balance(&referrer), net / 10);
+    s.assert_balances(MINTED - AMOUNT / 2, net - net / 10, fee_of(AMOUNT / 2), 0);
+}
+
+#[test]
+fn test_renewal_keeps_referrer_with_current_terms() {
+    let s = Setup::new();
+    let referrer = Address::generate(&s.env);
+    let id = s.try_create_referred(&referrer, true).unwrap();
+    assert_eq!(s.client.get_stream(&id).referral_fee_bps, 0);
+
+    s.client.set_referral_config(&ReferralConfig { fee_share_bps: 2_500, max_revenue_share_bps: 0 });
+    s.advance(DURATION);
+    let renewed = s.client.get_stream(&s.client.renew_stream(&id));
+    assert_eq!(renewed.referrer, Some(referrer));
+    assert_eq!(renewed.referral_fee_bps, 2_500);
+}
+
+#[test]
+fn test_referral_limits_are_enforced() {
+    let s = Setup::new();
+    assert_eq!(
+        s.client.try_set_referral_config(&ReferralConfig { fee_share_bps: 10_001, max_revenue_share_bps: 0 }),
+        Err(Ok(StreamError::InvalidReferral)),
+    );
+    s.client.set_referral_config(&ReferralConfig { fee_share_bps: 0, max_revenue_share_bps: 500 });
+    assert_eq!(s.client.try_set_referral_share(&s.creator, &501), Err(Ok(StreamError::InvalidReferral)));
+
+    // Nobody refers themselves
+    assert_eq!(s.try_create_referred(&s.subscriber, false), Err(StreamError::InvalidReferral));
+    assert_eq!(s.try_create_referred(&s.creator, false), Err(StreamError::InvalidReferral));
+
+    // A creator offer above a later, lower cap is clamped on new streams
+    s.client.set_referral_share(&s.creator, &500);
+    s.client.set_referral_config(&ReferralConfig { fee_share_bps: 0, max_revenue_share_bps: 200 });
+    let id = s.try_create_referred(&Address::generate(&s.env), false).unwrap();
+    assert_eq!(s.client.get_stream(&id).referral_revenue_bps, 200);
+}
+
+// === Trials and cliffs ===
+
+impl Setup<'_> {
+    fn try_create_with(&self, trial_seconds: u64, cliff_seconds: u64) -> Result<u64, StreamError> {
+        self.client
+            .try_create_stream(
+                &self.subscriber,
+                &self.creator,
+                &self.token.address,
+                &AMOUNT,
+                &DURATION,
+                &0,
+                &false,
+                &None,
+                &Some(StreamOptions { trial_seconds, cliff_seconds, ..StreamOptions::default() }),
+            )
+            .map(|id| id.unwrap())
+            .map_err(|err| err.unwrap())
+    }
+}
+
+#[test]
+fn test_trial_delays_accrual_and_extends_stream() {
+    let s = Setup::new();
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 500, max_cliff_seconds: 0 });
+    assert_eq!(s.env.auths()[0].0, s.creator);
+
+    let id = s.try_create_with(500, 0).unwrap();
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.trial_end, START + 500);
+    assert_eq!(stream.end_time, START + 500 + DURATION);
+    assert!(s.client.has_used_trial(&s.subscriber, &s.creator));
+
+    s.advance(500);
+    assert_eq!(s.client.get_withdrawable(&id), 0);
+    s.advance(DURATION / 4);
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT / 4 - fee_of(AMOUNT / 4));
+
+    s.advance(DURATION);
+    s.client.withdraw(&id);
+    s.assert_balances(MINTED - AMOUNT, AMOUNT - fee_of(AMOUNT), fee_of(AMOUNT), 0);
+}
+
+#[test]
+fn test_cancel_during_trial_refunds_everything() {
+    let s = Setup::new();
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 500, max_cliff_seconds: 0 });
+
+    let id = s.try_create_with(500, 0).unwrap();
+    s.advance(400);
+    assert_eq!(s.client.cancel(&id), (0, AMOUNT));
+    s.assert_balances(MINTED, 0, 0, 0);
+
+    // One trial per subscriber and creator
+    assert_eq!(s.try_create_with(500, 0), Err(StreamError::TrialAlreadyUsed));
+    s.try_create_with(0, 0).unwrap();
+}
+
+#[test]
+fn test_trial_and_cliff_must_be_offered() {
+    let s = Setup::new();
+    assert_eq!(s.try_create_with(1, 0), Err(StreamError::TermsNotOffered));
+    assert_eq!(s.try_create_with(0, 1), Err(StreamError::TermsNotOffered));
+
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 500, max_cliff_seconds: DURATION * 2 });
+    assert_eq!(s.try_create_with(501, 0), Err(StreamError::TermsNotOffered));
+    assert_eq!(s.try_create_with(0, DURATION + 1), Err(StreamError::InvalidDuration));
+    assert!(!s.client.has_used_trial(&s.subscriber, &s.creator));
+    s.assert_balances(MINTED, 0, 0, 0);
+}
+
+#[test]
+fn test_cliff_holds_payouts_and_refunds_cancel_in_full() {
+    let s = Setup::new();
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 0, max_cliff_seconds: DURATION / 2 });
+
+    let id = s.try_create_with(0, DURATION / 2).unwrap();
+    assert_eq!(s.client.get_stream(&id).cliff_end, START + DURATION / 2);
+
+    // Accrual runs but nothing can be paid out, and a cancel refunds it all
+    s.advance(DURATION / 4);
+    assert_eq!(s.client.get_withdrawable(&id), 0);
+    assert_eq!(s.client.try_withdraw(&id), Err(Ok(StreamError::CliffNotReached)));
+    assert_eq!(s.client.cancel(&id), (0, AMOUNT));
+    s.assert_balances(MINTED, 0, 0, 0);
+
+    // Past the cliff everything accrued so far is withdrawable
+    let id = s.try_create_with(0, DURATION / 2).unwrap();
+    s.advance(DURATION / 2);
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT / 2 - fee_of(AMOUNT / 2));
+    s.client.withdraw(&id);
+    assert_eq!(s.client.cancel(&id), (0, AMOUNT / 2));
+}
+
+#[test]
+fn test_creator_cannot_settle_within_cliff() {
+    let s = Setup::new();
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 0, max_cliff_seconds: DURATION / 2 });
+    let id = s.try_create_with(0, DURATION / 2).unwrap();
+
+    s.advance(DURATION / 4);
+    assert_eq!(s.client.terminate_stream(&id, &TerminationMode::Settle, &0), (0, AMOUNT));
+    s.assert_balances(MINTED, 0, 0, 0);
+}
+
+#[test]
+fn test_pause_within_cliff_keeps_the_cliff() {
+    let s = Setup::new();
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 0, max_cliff_seconds: DURATION / 2 });
+    let id = s.try_create_with(0, DURATION / 2).unwrap();
+
+    // Pause inside the cliff and stay paused past its original end
+    s.advance(DURATION / 4);
+    s.client.pause_stream(&id);
+    s.advance(DURATION / 2);
+    assert_eq!(s.client.get_withdrawable(&id), 0);
+    assert_eq!(s.client.try_withdraw(&id), Err(Ok(StreamError::CliffNotReached)));
+
+    // The cliff still has a quarter to run after the resume
+    s.client.resume_stream(&id);
+    assert_eq!(s.client.get_stream(&id).cliff_end, START + DURATION);
+    assert_eq!(s.client.try_withdraw(&id), Err(Ok(StreamError::CliffNotReached)));
+
+    s.advance(DURATION / 4);
+    assert_eq!(s.client.withdraw(&id), AMOUNT / 2 - fee_of(AMOUNT / 2));
+}
+
+#[test]
+fn test_change_tier_within_cliff_ends_the_cliff_with_the_stream() {
+    let s = Setup::new();
+    s.client.set_trial_policy(&s.creator, &TrialPolicy { max_trial_seconds: 0, max_cliff_seconds: DURATION / 2 });
+    let id = s.try_create_with(0, DURATION / 2).unwrap();
+
+    // Shorten the stream to end well inside the original cliff
+    s.advance(10);
+    s.client.change_tier(&id, &0, &AMOUNT, &100);
+    assert_eq!(s.client.get_stream(&id).cliff_end, START + 110);
+    s.advance(50);
+    assert_eq!(s.client.get_withdrawable(&id), 0);
+
+    // Once the stream has ended, cancelling pays the creator the finished period
+    s.advance(150);
+    let total = AMOUNT + AMOUNT / 100;
+    assert_eq!(s.client.cancel(&id), (total, 0));
+    assert_eq!(s.balance(&s.creator), total - fee_of(total));
+}
+
+// === Release schedules ===
+
+impl Setup<'_> {
+    fn try_create_scheduled(&self, schedule: ReleaseSchedule) -> Result<u64, StreamError> {
+        self.client
+            .try_create_stream(
+                &self.subscriber,
+                &self.creator,
+                &self.token.address,
+                &AMOUNT,
+                &DURATION,
+                &0,
+                &false,
+                &None,
+                &Some(StreamOptions { schedule, ..StreamOptions::default() }),
+            )
+            .map(|id| id.unwrap())
+            .map_err(|err| err.unwrap())
+    }
+
+    /// Amount released to the creator so far (for streams opened at zero fee)
+    fn released(&self, stream_id: u64) -> i128 {
+        self.client.get_withdrawable(&stream_id) + self.client.get_stream(&stream_id).withdrawn
+    }
+}
+
+#[test]
+fn test_stepped_schedule_releases_chunks_at_each_interval() {
+    let s = Setup::new();
+    s.client.set_platform_fee(&0);
+    let id = s.try_create_scheduled(ReleaseSchedule::Stepped(DURATION / 4)).unwrap();
+    assert_eq!(s.client.get_stream(&id).schedule, ReleaseSchedule::Stepped(DURATION / 4));
+
+    s.advance(1);
+    assert_eq!(s.released(id), AMOUNT / 4);
+    s.advance(DURATION / 4 - 1);
+    assert_eq!(s.released(id), AMOUNT / 4);
+    s.advance(1);
+    assert_eq!(s.released(id), AMOUNT / 2);
+
+    // Cancelling refunds only the chunks not yet released
+    assert_eq!(s.client.cancel(&id), (AMOUNT / 2, AMOUNT / 2));
+    s.assert_balances(MINTED - AMOUNT / 2, AMOUNT / 2, 0, 0);
+}
+
+#[test]
+fn test_stepped_schedule_keeps_its_steps_across_extensions() {
+    let s = Setup::new();
+    s.client.set_platform_fee(&0);
+    let id = s.try_create_scheduled(ReleaseSchedule::Stepped(DURATION / 4)).unwrap();
+
+    // Two chunks are out; extending must not release a third early
+    s.advance(DURATION / 4 + 50);
+    s.client.extend_stream(&id, &AMOUNT, &DURATION);
+    assert_eq!(s.released(id), AMOUNT / 2);
+
+    // The rest is spread over the steps left from the end of the released ones
+    s.advance(DURATION / 4);
+    let remaining_steps = 6;
+    assert_eq!(s.released(id), AMOUNT / 2 + (AMOUNT * 3 / 2) / remaining_steps);
+
+    s.advance(DURATION * 2);
+    s.client.withdraw(&id);
+    assert_eq!(s.client.get_stream(&id).withdrawn, AMOUNT * 2);
+}
+
+#[test]
+fn test_change_tier_keeps_the_schedule_valid() {
+    let s = Setup::new();
+    let id = s.try_create_scheduled(ReleaseSchedule::Stepped(DURATION / 4)).unwrap();
+
+    // A duration shorter than a step would release everything at once
+    s.advance(10);
+    assert_eq!(s.client.try_change_tier(&id, &0, &AMOUNT, &100), Err(Ok(StreamError::InvalidSchedule)));
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+
+    // The first step was released when it started; the new deposit waits for the next
+    s.client.change_tier(&id, &0, &AMOUNT, &(DURATION / 4));
+    let first_step = AMOUNT / 4 - fee_of(AMOUNT / 4);
+    s.advance(DURATION / 4 - 10);
+    assert_eq!(s.client.get_withdrawable(&id), first_step);
+    s.advance(1);
+    assert_eq!(s.client.get_withdrawable(&id), first_step + AMOUNT - fee_of(AMOUNT));
+}
+
+#[test]
+fn test_front_loaded_schedule_releases_share_up_front() {
+    let s = Setup::new();
+    s.client.set_platform_fee(&0);
+    let id = s.try_create_scheduled(ReleaseSchedule::FrontLoaded(2_500)).unwrap();
+
+    assert_eq!(s.released(id), 0);
+    s.advance(1);
+    let upfront = AMOUNT / 4;
+    assert_eq!(s.released(id), upfront + (AMOUNT - upfront) / DURATION as i128);
+
+    s.advance(DURATION / 2 - 1);
+    s.client.withdraw(&id);
+    let earned = upfront + (AMOUNT - upfront) / 2;
+    assert_eq!(s.client.get_stream(&id).withdrawn, earned);
+
+    // Later checkpoints do not release another up-front share
+    s.client.extend_stream(&id, &AMOUNT, &DURATION);
+    s.advance(1);
+    let unaccrued = AMOUNT * 2 - earned;
+    assert_eq!(s.released(id), earned + unaccrued / (DURATION as i128 * 3 / 2));
+}
+
+#[test]
+fn test_invalid_schedules_are_rejected() {
+    let s = Setup::new();
+    assert_eq!(s.try_create_scheduled(ReleaseSchedule::Stepped(0)), Err(StreamError::InvalidSchedule));
+    assert_eq!(s.try_create_scheduled(ReleaseSchedule::Stepped(DURATION + 1)), Err(StreamError::InvalidSchedule));
+    assert_eq!(s.try_create_scheduled(ReleaseSchedule::FrontLoaded(10_001)), Err(StreamError::InvalidSchedule));
+    s.assert_balances(MINTED, 0, 0, 0);
+}
+
+#[test]
+fn test_renewal_keeps_release_schedule() {
+    let s = Setup::new();
+    let id = s.client.create_stream(
+        &s.subscriber,
+        &s.creator,
+        &s.token.address,
+        &AMOUNT,
+        &DURATION,
+        &0,
+        &true,
+        &None,
+        &Some(StreamOptions { schedule: ReleaseSchedule::Stepped(DURATION / 2), ..StreamOptions::default() }),
+    );
+
+    s.advance(DURATION);
+    let new_id = s.client.renew_stream(&id);
+    assert_eq!(s.client.get_stream(&new_id).schedule, ReleaseSchedule::Stepped(DURATION / 2));
+}
+
+// === Subscriber pause ===
+
+#[test]
+fn test_pause_stops_accrual_and_shifts_end_time() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(DURATION / 4);
+    s.client.pause_stream(&id);
+    assert_eq!(s.env.auths()[0].0, s.subscriber);
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Paused);
+
+    // Nothing accrues while paused, but what accrued before can be withdrawn
+    s.advance(500);
+    let earned = AMOUNT / 4;
+    assert_eq!(s.client.get_withdrawable(&id), earned - fee_of(earned));
+    s.client.withdraw(&id);
+    assert_eq!(s.client.try_extend_stream(&id, &AMOUNT, &DURATION), Err(Ok(StreamError::StreamNotActive)));
+
+    let end_time = s.client.resume_stream(&id);
+    assert_eq!(end_time, START + DURATION + 500);
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Active);
+
+    s.advance(DURATION / 4);
+    assert_eq!(s.client.get_withdrawable(&id), AMOUNT / 4 - fee_of(AMOUNT / 4));
+
+    // The full deposit still streams out, just later
+    s.advance(DURATION / 2);
+    s.client.withdraw(&id);
+    assert_eq!(s.client.get_stream(&id).status, StreamStatus::Completed);
+    s.assert_balances(MINTED - AMOUNT, AMOUNT - fee_of(AMOUNT), fee_of(AMOUNT), 0);
+}
+
+#[test]
+fn test_cancel_while_paused_refunds_unstreamed_balance() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.advance(DURATION / 4);
+    s.client.pause_stream(&id);
+    s.advance(DURATION * 5);
+
+    let earned = AMOUNT / 4;
+    assert_eq!(s.client.cancel(&id), (earned, AMOUNT - earned));
+    s.assert_balances(MINTED - earned, earned - fee_of(earned), fee_of(earned), 0);
+    assert!(!s.client.has_active_stream(&s.subscriber, &s.creator));
+}
+
+#[test]
+fn test_pause_policy_limits_pauses_per_period() {
+    let s = Setup::new();
+    s.client.set_pause_policy(
+        &s.creator,
+        &PausePolicy { max_pause_seconds: 0, max_pauses: 1, period_seconds: DURATION / 2 },
+    );
+    assert_eq!(s.env.auths()[0].0, s.creator);
+    let id = s.create_default();
+
+    assert_eq!(s.client.try_resume_stream(&id), Err(Ok(StreamError::StreamNotPaused)));
+    s.client.pause_stream(&id);
+    assert_eq!(s.client.try_pause_stream(&id), Err(Ok(StreamError::StreamNotActive)));
+    s.client.resume_stream(&id);
+    assert_eq!(s.client.try_pause_stream(&id), Err(Ok(StreamError::PauseLimitReached)));
+
+    // A new window allows another pause
+    s.advance(DURATION / 2);
+    s.client.pause_stream(&id);
+    assert_eq!(s.client.get_stream(&id).pause_count, 1);
+}
+
+#[test]
+fn test_pause_resumes_by_itself_after_max_length() {
+    let s = Setup::new();
+    s.client.set_pause_policy(
+        &s.creator,
+        &PausePolicy { max_pause_seconds: 100, max_pauses: 0, period_seconds: 0 },
+    );
+    let id = s.create_default();
+
+    s.advance(DURATION / 4);
+    s.client.pause_stream(&id);
+
+    // Accrual picks up again once the pause runs out
+    s.advance(300);
+    let stream = s.client.get_stream(&id);
+    assert_eq!(stream.status, StreamStatus::Active);
+    assert_eq!(stream.end_time, START + DURATION + 100);
+    let earned = AMOUNT / 4 + 200 * (AMOUNT / DURATION as i128);
+    assert_eq!(s.client.get_withdrawable(&id), earned - fee_of(earned));
+    assert_eq!(s.client.try_resume_stream(&id), Err(Ok(StreamError::StreamNotPaused)));
+}
+
+// === Emergency pause ===
+
+#[test]
+fn test_pause_blocks_fund_flows() {
+    let s = Setup::new();
+    let id = s.create_renewable(None);
+    s.advance(DURATION / 2);
+
+    s.client.pause();
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert!(s.client.is_paused());
+
+    assert_eq!(s.client.try_withdraw(&id), Err(Ok(StreamError::ContractPaused)));
+    assert_eq!(s.client.try_withdraw_all(&s.creator, &0, &MAX_PAGE_SIZE), Err(Ok(StreamError::ContractPaused)));
+    assert_eq!(s.client.try_extend_stream(&id, &10, &10), Err(Ok(StreamError::ContractPaused)));
+
+    let other = Address::generate(&s.env);
+    let created = s.client.try_create_stream(
+        &other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+    assert_eq!(created, Err(Ok(StreamError::ContractPaused)));
+
+    s.advance(DURATION);
+    assert_eq!(s.client.try_renew_stream(&id), Err(Ok(StreamError::ContractPaused)));
+    s.advance(DEFAULT_GRACE_PERIOD);
+    assert_eq!(s.client.try_expire_stream(&id), Err(Ok(StreamError::ContractPaused)));
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+}
+
+#[test]
+fn test_cancel_works_while_paused() {
+    let s = Setup::new();
+    let id = s.create_default();
+    s.advance(100);
+
+    s.client.pause();
+    let (creator_side, refund) = s.client.cancel(&id);
+
+    assert_eq!(creator_side, 100_000);
+    assert_eq!(refund, 900_000);
+    s.assert_balances(MINTED - 100_000, 100_000 - fee_of(100_000), fee_of(100_000), 0);
+}
+
+#[test]
+fn test_terminate_while_paused_only_refunds() {
+    let s = Setup::new();
+    let id = s.create_default();
+    s.advance(DURATION / 2);
+
+    s.client.pause();
+    assert_eq!(
+        s.client.try_terminate_stream(&id, &TerminationMode::Settle, &0),
+        Err(Ok(StreamError::ContractPaused)),
+    );
+    s.assert_balances(MINTED - AMOUNT, 0, 0, AMOUNT);
+
+    let (creator_side, refund) = s.client.terminate_stream(&id, &TerminationMode::FullRefund, &0);
+    assert_eq!(creator_side, 0);
+    assert_eq!(refund, AMOUNT);
+    s.assert_balances(MINTED, 0, 0, 0);
+}
+
+#[test]
+fn test_unpause_restores_operation() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    s.client.pause();
+    s.client.unpause();
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert!(!s.client.is_paused());
+
+    s.advance(DURATION);
+    assert_eq!(s.client.withdraw(&id), AMOUNT - fee_of(AMOUNT));
+}
+
+// === Storage TTL ===
+
+impl Setup<'_> {
+    fn persistent_ttl(&self, key: &DataKey) -> u32 {
+        self.env.as_contract(&self.client.address, || self.env.storage().persistent().get_ttl(key))
+    }
+
+    fn instance_ttl(&self) -> u32 {
+        self.env.as_contract(&self.client.address, || self.env.storage().instance().get_ttl())
+    }
+
+    fn advance_ledgers(&self, ledgers: u32) {
+        self.env.ledger().with_mut(|li| li.sequence_number += ledgers);
+    }
+}
+
+#[test]
+fn test_create_stream_extends_ttl_past_end_and_grace() {
+    let s = Setup::new();
+    let id = s.create_default();
+
+    let expected = STREAM_TTL_BUFFER + ((DURATION + DEFAULT_GRACE_PERIOD) / SECONDS_PER_LEDGER) as u32;
+    assert_eq!(s.persistent_ttl(&DataKey::Stream(id)), expected);
+    let active_key = DataKey::ActiveStream(s.subscriber.clone(), s.creator.clone(), 0, s.token.address.clone());
+    assert_eq!(s.persistent_ttl(&active_key), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::PairActiveCount(s.subscriber.clone(), s.creator.clone())), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::SubscriberHistoryLen(s.subscriber.clone())), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::CreatorHistoryLen(s.creator.clone())), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::ActiveSubscriberCount(s.creator.clone())), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::ActiveSlots(id)), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::CreatorActive(s.creator.clone(), 0)), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::HistorySlots(id)), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::SubscriberHistory(s.subscriber.clone(), 0)), expected);
+    assert_eq!(s.persistent_ttl(&DataKey::CreatorHistory(s.creator.clone(), 0)), expected);
+    assert_eq!(s.instance_ttl(), INSTANCE_TTL_EXTEND);
+}
+
+#[test]
+fn test_long_streams_outlive_the_default_buffer() {
+    let s = Setup::new();
+    let half_year = 180 * 86_400;
+    let id = s.client.create_stream(
+        &s.subscriber, &s.creator, &s.token.address, &AMOUNT, &half_year, &0, &false, &None, &None,
+    );
+
+    let expected = STREAM_TTL_BUFFER + ((half_year + DEFAULT_GRACE_PERIOD) / SECONDS_PER_LEDGER) as u32;
+    assert_eq!(s.persistent_ttl(&DataKey::Stream(id)), expected);
+
+    // Never beyond what the network allows
+    let decade = 10 * 365 * 86_400;
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    let id = s.client.create_stream(
+        &other, &s.creator, &s.token.address, &AMOUNT, &decade, &0, &false, &None, &None,
+    );
+    let max_ttl = s.env.as_contract(&s.client.address, || s.env.storage().max_ttl());
+    assert_eq!(s.persistent_ttl(&DataKey::Stream(id)), max_ttl);
+}
+
+#[test]
+fn test_bump_stream_restores_ttl() {
+    let s = Setup::new();
+    let treasury = Address::generate(&s.env);
+    s.client.set_payout_address(&s.creator, &treasury);
+    let payout_key = DataKey::PayoutAddress(s.creator.clone());
+    let id = s.create_default();
+    let initial = s.persistent_ttl(&DataKey::Stream(id));
+
+    s.advance_ledgers(2 * LEDGERS_PER_DAY);
+    assert_eq!(s.persistent_ttl(&DataKey::Stream(id)), initial - 2 * LEDGERS_PER_DAY);
+
+    // Reading a stream extends its own entries but leaves the creator's config alone
+    let config_ttl = s.persistent_ttl(&payout_key);
+    s.client.get_withdrawable(&id);
+    assert_eq!(s.persistent_ttl(&DataKey::Stream(id)), initial);
+    assert_eq!(s.persistent_ttl(&payout_key), config_ttl);
+
+    s.advance_ledgers(2 * LEDGERS_PER_DAY);
+    s.client.bump_stream(&id);
+
+    assert_eq!(s.persistent_ttl(&DataKey::Stream(id)), initial);
+    assert_eq!(s.persistent_ttl(&DataKey::CreatorHistoryLen(s.creator.clone())), initial);
+    assert_eq!(s.persistent_ttl(&payout_key), initial);
+
+    // Instance storage is only re-extended once it drops under the threshold
+    assert_eq!(s.instance_ttl(), INSTANCE_TTL_EXTEND - 4 * LEDGERS_PER_DAY);
+    s.advance_ledgers(20 * LEDGERS_PER_DAY);
+    s.client.bump_stream(&id);
+    assert_eq!(s.instance_ttl(), INSTANCE_TTL_EXTEND);
+}
+
+#[test]
+fn test_bump_stream_unknown_stream_fails() {
+    let s = Setup::new();
+    assert_eq!(s.client.try_bump_stream(&9), Err(Ok(StreamError::StreamNotFound)));
+}
+
+#[test]
+fn test_admin_calls_extend_instance_ttl() {
+    let s = Setup::new();
+    s.advance_ledgers(25 * LEDGERS_PER_DAY);
+    assert!(s.instance_ttl() < INSTANCE_TTL_THRESHOLD);
+
+    s.client.set_grace_period(&60);
+    assert_eq!(s.instance_ttl(), INSTANCE_TTL_EXTEND);
+}
+
+// === Upgrades ===
+
+/// Rewrites a stored stream and its indexes as the original (v1) layout stored them
+fn downgrade_to_v1(s: &Setup, stream_id: u64) {
+    s.env.as_contract(&s.client.address, || {
+        let storage = s.env.storage().persistent();
+        let key = DataKey::Stream(stream_id);
+        let stream: Stream = storage.get(&key).unwrap();
+
+        let mut fields: Map<Symbol, Val> = storage.get(&key).unwrap();
+        fields.remove(Symbol::new(&s.env, "renewal_allowance"));
+        fields.remove(Symbol::new(&s.env, "fee_bps"));
+        fields.remove(Symbol::new(&s.env, "accrued"));
+        fields.remove(Symbol::new(&s.env, "checkpoint_time"));
+        fields.remove(Symbol::new(&s.env, "termination_reason"));
+        fields.remove(Symbol::new(&s.env, "paused_at"));
+        fields.remove(Symbol::new(&s.env, "pause_limit"));
+        fields.remove(Symbol::new(&s.env, "pause_count"));
+        fields.remove(Symbol::new(&s.env, "pause_window_start"));
+        fields.remove(Symbol::new(&s.env, "trial_end"));
+        fields.remove(Symbol::new(&s.env, "cliff_end"));
+        fields.remove(Symbol::new(&s.env, "schedule"));
+        fields.remove(Symbol::new(&s.env, "accrual_start"));
+        fields.remove(Symbol::new(&s.env, "referrer"));
+        fields.remove(Symbol::new(&s.env, "referral_fee_bps"));
+        fields.remove(Symbol::new(&s.env, "referral_revenue_bps"));
+        fields.remove(Symbol::new(&s.env, "cycle_amount"));
+        storage.set(&key, &fields);
+
+        // v1 kept one active stream per pair, one flat list per address and no active sets
+        storage.remove(&DataKey::ActiveStream(
+            stream.subscriber.clone(),
+            stream.creator.clone(),
+            stream.tier_id,
+            stream.token.clone(),
+        ));
+        storage.remove(&DataKey::PairActiveCount(stream.subscriber.clone(), stream.creator.clone()));
+        storage.remove(&DataKey::TierActiveCount(stream.subscriber.clone(), stream.creator.clone(), stream.tier_id));
+        if stream.status == StreamStatus::Active {
+            storage.set(&LegacyDataKey::ActiveStream(stream.subscriber.clone(), stream.creator.clone()), &stream_id);
+        }
+        storage.remove(&DataKey::ActiveSlots(stream_id));
+        storage.remove(&DataKey::HistorySlots(stream_id));
+        for (address, is_subscriber) in [(&stream.subscriber, true), (&stream.creator, false)] {
+            let (len_key, page_key, active_len_key, legacy_key) = if is_subscriber {
+                (
+                    DataKey::SubscriberHistoryLen(address.clone()),
+                    DataKey::SubscriberHistory(address.clone(), 0),
+                    DataKey::SubscriberActiveLen(address.clone()),
+                    LegacyDataKey::SubscriberStreams(address.clone()),
+                )
+            } else {
+                (
+                    DataKey::CreatorHistoryLen(address.clone()),
+                    DataKey::CreatorHistory(address.clone(), 0),
+                    DataKey::CreatorActiveLen(address.clone()),
+                    LegacyDataKey::CreatorStreams(address.clone()),
+                )
+            };
+            let active_len: u32 = storage.get(&active_len_key).unwrap_or(0);
+            for slot in 0..active_len {
+                storage.remove(&if is_subscriber {
+                    DataKey::SubscriberActive(address.clone(), slot)
+                } else {
+                    DataKey::CreatorActive(address.clone(), slot)
+                });
+            }
+            storage.remove(&len_key);
+            storage.remove(&page_key);
+            storage.remove(&active_len_key);
+
+            let mut legacy: soroban_sdk::Vec<u64> = storage.get(&legacy_key).unwrap_or(soroban_sdk::Vec::new(&s.env));
+            legacy.push_back(stream_id);
+            storage.set(&legacy_key, &legacy);
+        }
+
+        s.env.storage().instance().remove(&DataKey::SchemaVersion);
+    });
+}
+
+#[test]
+fn test_initialize_records_schema_version() {
+    let s = Setup::new();
+    assert_eq!(s.client.get_schema_version(), SCHEMA_VERSION);
+    assert_eq!(s.client.migrate(&10), 0);
+}
+
+#[test]
+fn test_migrate_upgrades_legacy_streams_in_batches() {
+    let s = Setup::new();
+    let first = s.create_default();
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    let second = s.client.create_stream(
+        &other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+
+    downgrade_to_v1(&s, first);
+    downgrade_to_v1(&s, second);
+    assert_eq!(s.client.get_schema_version(), 1);
+    assert_eq!(s.client.try_get_stream(&first).err(), Some(Ok(StreamError::MigrationPending)));
+
+    // Streams wait for the migration rather than trapping
+    s.advance(DURATION / 2);
+    assert_eq!(s.client.try_cancel(&first), Err(Ok(StreamError::MigrationPending)));
+    assert_eq!(s.client.try_withdraw(&second), Err(Ok(StreamError::MigrationPending)));
+
+    assert_eq!(s.client.migrate(&1), 1);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(s.client.get_schema_version(), 1);
+    assert_eq!(s.client.get_stream(&first).renewal_allowance.remaining_cycles, 0);
+    assert_eq!(s.client.get_stream(&first).fee_bps, DEFAULT_PLATFORM_FEE_BPS);
+    assert_eq!(s.client.get_stream(&first).checkpoint_time, START);
+    assert_eq!(s.client.try_get_stream(&second).err(), Some(Ok(StreamError::MigrationPending)));
+
+    // New streams wait until the indexes have been rebuilt
+    let late = Address::generate(&s.env);
+    let blocked = s.client.try_create_stream(
+        &s.subscriber, &late, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+    );
+    assert_eq!(blocked, Err(Ok(StreamError::MigrationPending)));
+
+    assert_eq!(s.client.migrate(&1), 0);
+    assert_eq!(s.client.get_schema_version(), SCHEMA_VERSION);
+
+    // Indexes were rebuilt in order and the flat lists dropped
+    assert_eq!(s.client.get_creator_streams(&s.creator, &0, &10), soroban_sdk::vec![&s.env, first, second]);
+    assert_eq!(s.client.get_active_creator_streams(&s.creator, &0, &10).len(), 2);
+    assert_eq!(s.client.get_subscriber_streams(&other, &0, &10), soroban_sdk::vec![&s.env, second]);
+    assert_eq!(s.client.get_active_stream(&other, &s.creator, &0, &s.token.address), Some(second));
+    assert!(s.client.has_active_stream(&s.subscriber, &s.creator));
+    s.env.as_contract(&s.client.address, || {
+        assert!(!s.env.storage().persistent().has(&LegacyDataKey::CreatorStreams(s.creator.clone())));
+    });
+
+    // Migrated streams keep working and keep their funds
+    s.advance(DURATION / 2);
+    s.client.withdraw(&first);
+    s.client.withdraw(&second);
+    assert_eq!(s.contract_balance(), 0);
+    assert_eq!(s.balance(&s.creator), 2 * (AMOUNT - fee_of(AMOUNT)));
+}
+
+#[test]
+fn test_migrate_records_history_slots_across_pages() {
+    let s = Setup::new();
+    s.env.budget().reset_unlimited();
+    let first = s.create_default();
+    let other = Address::generate(&s.env);
+    s.fund(&other, MINTED);
+    let mut last = first;
+    for _ in 0..60 {
+        last = s.client.create_stream(
+            &other, &s.creator, &s.token.address, &AMOUNT, &DURATION, &0, &false, &None, &None,
+        );
+        s.client.cancel(&last);
+    }
+
+    // v11 streams carried no history positions
+    s.env.as_contract(&s.client.address, || {
+        for id in first..=last {
+            s.env.storage().persistent().remove(&DataKey::HistorySlots(id));
+        }
+        s.env.storage().instance().set(&DataKey::SchemaVersion, &11u32);
+    });
+    assert_eq!(s.client.migrate(&100), 0);
+
+    let slots = |id: u64| -> HistorySlots {
+        s.env.as_contract(&s.client.address, || {
+            s.env.storage().persistent().get(&DataKey::HistorySlots(id)).unwrap()
+        })
+    };
+    assert_eq!(slots(first), HistorySlots { subscriber_index: 0, creator_index: 0 });
+    assert_eq!(slots(first + 50), HistorySlots { subscriber_index: 49, creator_index: 50 });
+    assert_eq!(slots(last), HistorySlots { subscriber_index: 59, creator_index: 60 });
+}
+
+#[test]
+fn test_upgrade_requires_uploaded_wasm() {
+    let s = Setup::new();
+    let unknown = BytesN::from_array(&s.env, &[7; 32]);
+    assert!(s.client.try_upgrade(&unknown).is_err());
+}
+
+#[test]
+fn test_upgrade_requires_admin_auth() {
+    let s = Setup::new();
+    // Smallest module the host accepts: just the environment interface version
+    let mut wasm = std::vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x00, 0x1e, 17];
+    wasm.extend_from_slice(b"contractenvmetav0");
+    wasm.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 21, 0, 0, 0, 0]);
+    let hash = s.env.deployer().upload_contract_wasm(soroban_sdk::Bytes::from_slice(&s.env, &wasm));
+
+    s.env.set_auths(&[]);
+    assert!(s.client.try_upgrade(&hash).is_err());
+
+    s.env.mock_all_auths();
+    s.client.upgrade(&hash);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+}
@@ -0,0 +1,320 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use proptest::prelude::*;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::{Client as TokenClient, StellarAssetClient},
+    Address, Env,
+};
+// prop_oneof! builds its arm list with vec! once it has more than ten arms
+use std::vec;
+use std::vec::Vec as StdVec;
+
+/// Balance minted to every subscriber before a run
+const MINTED: i128 = 1_000_000_000_000;
+const SUBSCRIBERS: usize = 3;
+const CREATORS: usize = 2;
+
+/// One step of a randomly generated scenario
+///
+/// Stream and party fields are indexes that get reduced modulo whatever
+/// exists at the time the step runs.
+#[derive(Clone, Debug)]
+enum Op {
+    Create {
+        subscriber: usize,
+        creator: usize,
+        amount: i128,
+        duration: u64,
+        schedule: u8,
+        referred: bool,
+        auto_renew: bool,
+    },
+    Withdraw { stream: usize },
+    Extend { stream: usize, amount: i128, seconds: u64 },
+    ChangeTier { stream: usize, amount: i128, duration: u64 },
+    Cancel { stream: usize },
+    Terminate { stream: usize, settle: bool },
+    Expire { stream: usize },
+    Renew { stream: usize },
+    Pause { stream: usize },
+    Resume { stream: usize },
+    Advance { seconds: u64 },
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..SUBSCRIBERS, 0..CREATORS, 1i128..5_000_000, 1u64..10_000, 0u8..3, any::<bool>(), any::<bool>())
+            .prop_map(|(subscriber, creator, amount, duration, schedule, referred, auto_renew)| {
+                Op::Create { subscriber, creator, amount, duration, schedule, referred, auto_renew }
+            }),
+        any::<usize>().prop_map(|stream| Op::Withdraw { stream }),
+        (any::<usize>(), 1i128..5_000_000, 1u64..10_000)
+            .prop_map(|(stream, amount, seconds)| Op::Extend { stream, amount, seconds }),
+        (any::<usize>(), 1i128..5_000_000, 1u64..10_000)
+            .prop_map(|(stream, amount, duration)| Op::ChangeTier { stream, amount, duration }),
+        any::<usize>().prop_map(|stream| Op::Cancel { stream }),
+        (any::<usize>(), any::<bool>()).prop_map(|(stream, settle)| Op::Terminate { stream, settle }),
+        any::<usize>().prop_map(|stream| Op::Expire { stream }),
+        any::<usize>().prop_map(|stream| Op::Renew { stream }),
+        any::<usize>().prop_map(|stream| Op::Pause { stream }),
+        any::<usize>().prop_map(|stream| Op::Resume { stream }),
+        (0u64..5_000).prop_map(|seconds| Op::Advance { seconds }),
+    ]
+}
+
+struct Harness<'a> {
+    env: Env,
+    client: StreamingPaymentsContractClient<'a>,
+    token: TokenClient<'a>,
+    platform: Address,
+    subscribers: StdVec<Address>,
+    creators: StdVec<Address>,
+    streams: StdVec<u64>,
+    /// Streams retired by a renewal, which may still hold unwithdrawn accrual
+    renewed: StdVec<u64>,
+}
+
+impl Harness<'_> {
+    fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Long scenarios share one budget; cost is not what is under test here
+        env.budget().reset_unlimited();
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+        let admin = Address::generate(&env);
+        let platform = Address::generate(&env);
+        let sac = env.register_stellar_asset_contract_v2(Address::generate(&env));
+        let token = TokenClient::new(&env, &sac.address());
+        let minter = StellarAssetClient::new(&env, &sac.address());
+
+        let subscribers: StdVec<Address> = (0..SUBSCRIBERS).map(|_| Address::generate(&env)).collect();
+        let creators: StdVec<Address> = (0..CREATORS).map(|_| Address::generate(&env)).collect();
+        for subscriber in &subscribers {
+            minter.mint(subscriber, &MINTED);
+        }
+
+        let contract_id = env.register_contract(None, StreamingPaymentsContract);
+        let client = StreamingPaymentsContractClient::new(&env, &contract_id);
+        client.initialize(&admin, &platform, &None, &None);
+        client.set_token_config(
+            &sac.address(),
+            &TokenConfig { min_deposit: 0, min_duration: 0, max_deposit: None },
+        );
+
+        // One creator caps pauses so pauses that lapse by themselves get exercised
+        client.set_pause_policy(
+            &creators[1],
+            &PausePolicy { max_pause_seconds: 2_000, max_pauses: 2, period_seconds: 5_000 },
+        );
+
+        // Referrers get part of the fee, and of one creator's revenue
+        client.set_referral_config(&ReferralConfig { fee_share_bps: 5_000, max_revenue_share_bps: 2_000 });
+        client.set_referral_share(&creators[1], &1_500);
+
+        // The other creator shares its payouts, so split payouts get exercised
+        client.set_revenue_split(
+            &creators[0],
+            &soroban_sdk::vec![
+                &env,
+                SplitShare { recipient: creators[0].clone(), bps: 7_000 },
+                SplitShare { recipient: creators[1].clone(), bps: 3_000 },
+            ],
+            &MAX_PAGE_SIZE,
+        );
+
+        // Fees drop as creators build volume, and one creator has its own fee
+        client.set_volume_fee_tiers(
+            &sac.address(),
+            &soroban_sdk::vec![
+                &env,
+                VolumeFeeTier { min_volume: 1_000_000, fee_bps: 150 },
+                VolumeFeeTier { min_volume: 5_000_000, fee_bps: 50 },
+            ],
+        );
+        client.set_creator_fee_override(&creators[0], &300);
+
+        Harness {
+            env,
+            client,
+            token,
+            platform,
+            subscribers,
+            creators,
+            streams: StdVec::new(),
+            renewed: StdVec::new(),
+        }
+    }
+
+    fn pick(&self, index: usize) -> Option<u64> {
+        if self.streams.is_empty() {
+            None
+        } else {
+            Some(self.streams[index % self.streams.len()])
+        }
+    }
+
+    /// Runs one step; rejected operations are fine, they just must not leak funds
+    fn apply(&mut self, op: &Op) {
+        match *op {
+            Op::Create { subscriber, creator, amount, duration, schedule, referred, auto_renew } => {
+                let schedule = match schedule {
+                    0 => ReleaseSchedule::Linear,
+                    1 => ReleaseSchedule::Stepped(duration.div_ceil(4)),
+                    _ => ReleaseSchedule::FrontLoaded(3_000),
+                };
+                let result = self.client.try_create_stream(
+                    &self.subscribers[subscriber],
+                    &self.creators[creator],
+                    &self.token.address,
+                    &amount,
+                    &duration,
+                    &0,
+                    &auto_renew,
+                    // Keepers may renew twice at the same price
+                    &auto_renew.then_some(RenewalAllowance { remaining_cycles: 2, max_amount_per_cycle: amount }),
+                    &Some(StreamOptions {
+                        schedule,
+                        // Another subscriber refers, so referral payouts stay on the books
+                        referrer: referred.then(|| self.subscribers[(subscriber + 1) % SUBSCRIBERS].clone()),
+                        ..StreamOptions::default()
+                    }),
+                );
+                if let Ok(Ok(id)) = result {
+                    self.streams.push(id);
+                }
+            }
+            Op::Withdraw { stream } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_withdraw(&id);
+                }
+            }
+            Op::Extend { stream, amount, seconds } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_extend_stream(&id, &amount, &seconds);
+                }
+            }
+            Op::ChangeTier { stream, amount, duration } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_change_tier(&id, &0, &amount, &duration);
+                }
+            }
+            Op::Cancel { stream } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_cancel(&id);
+                }
+            }
+            Op::Terminate { stream, settle } => {
+                if let Some(id) = self.pick(stream) {
+                    let mode = if settle { TerminationMode::Settle } else { TerminationMode::FullRefund };
+                    let _ = self.client.try_terminate_stream(&id, &mode, &0);
+                }
+            }
+            Op::Expire { stream } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_expire_stream(&id);
+                }
+            }
+            Op::Renew { stream } => {
+                if let Some(id) = self.pick(stream) {
+                    // Keepers renew as soon as the period is over
+                    let end_time = self.client.get_stream(&id).end_time;
+                    self.env.ledger().with_mut(|li| li.timestamp = li.timestamp.max(end_time));
+                    if let Ok(Ok(new_id)) = self.client.try_renew_stream(&id) {
+                        self.renewed.push(id);
+                        self.streams.push(new_id);
+                    }
+                }
+            }
+            Op::Pause { stream } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_pause_stream(&id);
+                }
+            }
+            Op::Resume { stream } => {
+                if let Some(id) = self.pick(stream) {
+                    let _ = self.client.try_resume_stream(&id);
+                }
+            }
+            Op::Advance { seconds } => {
+                self.env.ledger().with_mut(|li| li.timestamp += seconds);
+            }
+        }
+    }
+
+    /// Conservation of funds: escrow matches what open and renewed streams
+    /// still owe, other completed streams paid out exactly, and no tokens
+    /// appeared or vanished; the active indexes agree with stream statuses
+    fn check_invariants(&self, step: usize, op: &Op) {
+        let mut owed: i128 = 0;
+        let mut active: StdVec<(Address, u64)> = StdVec::new();
+        for id in &self.streams {
+            let stream = self.client.get_stream(id);
+            assert!(
+                stream.withdrawn <= stream.total_amount,
+                "step {step} ({op:?}): stream {id} paid out more than deposited",
+            );
+            match stream.status {
+                StreamStatus::Cancelled | StreamStatus::Terminated => {}
+                // A renewal retires the stream before its accrual is withdrawn
+                StreamStatus::Completed if self.renewed.contains(id) => {
+                    owed += stream.total_amount - stream.withdrawn;
+                }
+                StreamStatus::Completed => assert_eq!(
+                    stream.withdrawn, stream.total_amount,
+                    "step {step} ({op:?}): completed stream {id} left funds behind",
+                ),
+                StreamStatus::Active | StreamStatus::Paused => {
+                    owed += stream.total_amount - stream.withdrawn;
+                    active.push((stream.creator.clone(), *id));
+                }
+            }
+        }
+
+        // Active sets hold exactly the active and paused streams
+        for creator in &self.creators {
+            let mut expected: StdVec<u64> =
+                active.iter().filter(|(c, _)| c == creator).map(|(_, id)| *id).collect();
+            let mut indexed: StdVec<u64> = StdVec::new();
+            loop {
+                let page = self.client.get_active_creator_streams(creator, &(indexed.len() as u32), &MAX_PAGE_SIZE);
+                if page.is_empty() {
+                    break;
+                }
+                indexed.extend(page.iter());
+            }
+            expected.sort();
+            indexed.sort();
+            assert_eq!(indexed, expected, "step {step} ({op:?}): active set out of sync");
+        }
+
+        let escrow = self.token.balance(&self.client.address);
+        assert_eq!(escrow, owed, "step {step} ({op:?}): escrow does not match open streams");
+
+        let mut total = escrow + self.token.balance(&self.platform);
+        for address in self.subscribers.iter().chain(self.creators.iter()) {
+            total += self.token.balance(address);
+        }
+        assert_eq!(
+            total,
+            MINTED * SUBSCRIBERS as i128,
+            "step {step} ({op:?}): tokens were created or destroyed",
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_funds_are_conserved(ops in prop::collection::vec(op_strategy(), 1..40)) {
+        let mut harness = Harness::new();
+        for (step, op) in ops.iter().enumerate() {
+            harness.apply(op);
+            harness.check_invariants(step, op);
+        }
+    }
+}